@@ -1,4 +1,5 @@
 use std::fmt;
+use std::future::Future;
 use futures::{SinkExt, StreamExt};
 use futures::stream::{SplitSink, SplitStream};
 use reqwest::{Response, StatusCode};
@@ -181,6 +182,28 @@ impl Client {
         Ok(serde_json::from_str(&data.to_string()).unwrap())
     }
 
+    /// The same query as `get_users_by_ids`, but the request doesn't borrow the client, so it can run
+    /// in a spawned task. Expired tokens aren't refreshed here, the failed batch is retried later
+    pub fn get_users_by_ids_detached(&mut self, user_ids: Vec<UserId>) -> impl Future<Output = ApiResult<UserSearchResults>> + Send + 'static {
+        let client = self.client.clone();
+        let authorization = self.get_authorization_header();
+        async move {
+            let res = client
+                .post(format!("http://{}/users/batch-query", USER_SERVICE_API_URL))
+                .header("Authorization", authorization)
+                .json(&GetUsersByIdsRequest { user_ids })
+                .send()
+                .await
+                .map_err(|e| ApiError::RequestError(e.to_string()))?;
+            if !res.status().is_success() {
+                return Err(Client::error_from_response(res).await);
+            }
+            res.json::<UserSearchResults>()
+                .await
+                .map_err(|e| ApiError::DataError(e.to_string()))
+        }
+    }
+
     pub async fn get_chats(&mut self) -> ApiResult<ChatSearchResults> {
         let rp = RequestParams {
             uri: format!("http://{}/chats", MESSAGE_SERVICE_API_URL),
@@ -219,10 +242,28 @@ impl Client {
             uri: format!("http://{}/chats/{}/read", MESSAGE_SERVICE_API_URL, chat_id),
             ..Default::default()
         };
-        match self.post(rp).await {
-            Ok(_) => {}
-            Err(_) => {}
-        }
+        let _ = self.post(rp).await;
+    }
+
+    pub async fn get_pinned_chats(&mut self) -> ApiResult<PinnedChats> {
+        let rp = RequestParams {
+            uri: format!("http://{}/chats/pinned", MESSAGE_SERVICE_API_URL),
+            ..Default::default()
+        };
+        let res = self.get(rp).await?;
+        res.json::<PinnedChats>()
+            .await
+            .map_err(|e| ApiError::DataError(e.to_string()))
+    }
+
+    pub async fn set_pinned_chats(&mut self, pinned_chats: &PinnedChats) -> ApiResult<()> {
+        let rp = RequestParams {
+            uri: format!("http://{}/chats/pinned", MESSAGE_SERVICE_API_URL),
+            body: Some(serde_json::to_value(pinned_chats).unwrap()),
+            ..Default::default()
+        };
+        self.post(rp).await?;
+        Ok(())
     }
 
     pub async fn search_users(&mut self, username: String) -> ApiResult<UserSearchResults> {
@@ -240,15 +281,15 @@ impl Client {
         Ok(users)
     }
 
-    pub async fn send_message(&mut self, message: NewMessage) {
+    pub async fn send_message(&mut self, message: NewMessage) -> ApiResult<()> {
         let send_message = self
             .write_message_ws
             .as_mut()
-            .expect("Unauthenticated")
+            .ok_or(ApiError::Unauthenticated)?
             .send(Message::Text(serde_json::to_string(&message).unwrap()));
         send_message
             .await
-            .expect("Failed to send message");
+            .map_err(|e| ApiError::RequestError(e.to_string()))
     }
 
     pub async fn create_chat(&mut self, chat: NewChatModel) -> ApiResult<ChatModel> {
@@ -324,16 +365,25 @@ impl Client {
                 }
             }
             if !res.status().is_success() {
-                let data = res.json::<serde_json::Value>()
-                    .await
-                    .map_err(|e| ApiError::DataError(e.to_string()))?;
-                return Err(ApiError::RequestError(data["detail"].to_string()));
+                return Err(Self::error_from_response(res).await);
             }
 
             return Ok(res);
         }
     }
 
+    async fn error_from_response(res: Response) -> ApiError {
+        let status = res.status();
+        let detail = match res.json::<serde_json::Value>().await {
+            Ok(data) => data["detail"].to_string(),
+            Err(e) => return ApiError::DataError(e.to_string()),
+        };
+        if status == StatusCode::NOT_FOUND || status == StatusCode::METHOD_NOT_ALLOWED {
+            return ApiError::NotFound(detail);
+        }
+        ApiError::RequestError(detail)
+    }
+
     fn should_refresh_tokens(&mut self, rp: &mut RequestParams, res: &Response) -> bool {
         res.status() == StatusCode::UNAUTHORIZED && rp.can_reauthenticate && self.auth_tokens.is_some()
     }
@@ -359,10 +409,7 @@ impl Client {
                 }
             }
             if !res.status().is_success() {
-                let data = res.json::<serde_json::Value>()
-                    .await
-                    .map_err(|e| ApiError::DataError(e.to_string()))?;
-                return Err(ApiError::RequestError(data["detail"].to_string()));
+                return Err(Self::error_from_response(res).await);
             }
 
             return Ok(res);
@@ -380,7 +427,7 @@ impl Client {
         };
         let res = self.
             client
-            .post(&format!("http://{}/refresh-token", AUTH_SERVICE_API_URL))
+            .post(format!("http://{}/refresh-token", AUTH_SERVICE_API_URL))
             .json(&refresh_token_data)
             .send()
             .await
@@ -473,6 +520,8 @@ type ApiResult<T> = Result<T, ApiError>;
 #[derive(Debug, Clone)]
 pub enum ApiError {
     Unauthenticated,
+    /// The resource or the endpoint doesn't exist on the server
+    NotFound(String),
     RequestError(String),
     DataError(String),
 }
@@ -481,6 +530,7 @@ impl fmt::Display for ApiError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             ApiError::Unauthenticated => write!(f, "Unauthenticated"),
+            ApiError::NotFound(e) => write!(f, "Not found: {}", e),
             ApiError::RequestError(e) => write!(f, "Request error: {}", e),
             ApiError::DataError(e) => write!(f, "Data error: {}", e),
         }
@@ -1,15 +1,23 @@
 use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
 use crossterm::event::KeyEvent;
-use crate::{api, factory, helpers, storage, window};
+use tokio::sync::mpsc::UnboundedSender;
+use crate::{api, command, export, factory, helpers, storage, window};
 use crate::api::ApiError;
 use crate::chat::builder::ChatBuilder;
-use crate::schemas::{ChatModel, MessageModel, NewChatModel, NewMessage, User};
-use crate::chat::Chat;
+use crate::schemas::{ChatModel, MessageModel, NewChatModel, NewMessage, PinnedChats, User};
+use crate::chat::{Chat, DeliveryState, MessageSearchHit, MuteDuration};
+use crate::chat::index::tokenize;
+use crate::command::Command;
+use crate::event::Event;
+use crate::export::ExportTarget;
+use crate::i18n::Strings;
+use crate::settings::{Settings, SettingsWatcher};
 use crate::chat::manager::ChatManager;
-use crate::helpers::types::{ChatId, TextInput};
+use crate::helpers::types::{ChatId, TextInput, UserId};
 use crate::window::InputEntity;
 use crate::window::login::{LoginTabs, LoginWindow};
-use crate::window::main::MainWindow;
+use crate::window::main::{MainWindow, SearchMode};
 
 pub struct App {
     pub login_window: LoginWindow,
@@ -18,34 +26,63 @@ pub struct App {
     active_window: Windows,
     api_client: api::Client,
     chat_builder: ChatBuilder,
+    /// Whether the server supports pinned chats, otherwise they are only stored locally
+    pins_on_server: bool,
+    /// When the search input last changed, the user search is sent to the server once it settles
+    search_requested_at: Option<Instant>,
     should_quit: bool,
+    settings: Settings,
+    settings_watcher: SettingsWatcher,
+    status: Option<Status>,
     user: Option<User>,
 }
 
+/// A short-lived message shown in the footer instead of the hints
+pub struct Status {
+    pub text: String,
+    pub is_error: bool,
+    created_at: Instant,
+}
+
+const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(300);
+/// Sent messages not echoed back by the server within this time are shown as failed
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
 impl App {
     pub async fn new(
         mut api_client: api::Client,
+        settings: Settings,
     ) -> Self {
         let mut chat_manager = ChatManager::new();
+        chat_manager.set_folders(settings.folders.clone());
         let mut chat_builder = factory::get_chat_builder(vec![], get_current_user());
         let mut user = None;
+        let mut pins_on_server = false;
 
         if api_client.is_authenticated() {
-            let (chat_models, messages) = Self::load_chats_and_messages(&mut api_client).await;
-            App::save_new_users_data(&mut api_client, &mut chat_builder, &chat_models).await;
-            chat_manager.add_chats(chat_builder.build_chats_from_models(chat_models));
-            chat_manager.add_messages(chat_builder.build_messages_from_models(messages));
+            let pinned_chat_ids;
+            (pinned_chat_ids, pins_on_server) = Self::load_pinned_chats(&mut api_client).await;
+            chat_manager.set_pinned_chat_ids(pinned_chat_ids);
+            chat_manager.set_archived_chat_ids(storage::load_archived_chats());
+            chat_manager.set_mutes(storage::load_muted_chats());
+            App::load_chats(&mut api_client, &mut chat_builder, &mut chat_manager).await;
 
             user = get_current_user();
         }
 
         Self {
             login_window: LoginWindow::default(),
-            main_window: MainWindow::new(chat_manager),
+            main_window: MainWindow::new(chat_manager, storage::load_drafts()),
             active_window: if !api_client.is_authenticated() { Windows::Login } else { Windows::Main },
             api_client,
             chat_builder,
+            pins_on_server,
+            search_requested_at: None,
             should_quit: false,
+            settings,
+            settings_watcher: SettingsWatcher::new(storage::get_settings_file_path()),
+            status: None,
             user,
         }
     }
@@ -54,9 +91,74 @@ impl App {
         self.api_client.is_authenticated()
     }
 
-    pub fn tick(&mut self) {}
+    pub fn tick(&mut self) {
+        if self.status.as_ref().is_some_and(|status| status.created_at.elapsed() > STATUS_TIMEOUT) {
+            self.status = None;
+        }
+        if self.main_window.chat_manager.expire_mutes() {
+            let result = storage::store_muted_chats(self.main_window.chat_manager.get_mutes());
+            self.report_save_error(result);
+        }
+        self.main_window.chat_manager.fail_pending_messages(DELIVERY_TIMEOUT);
+        if let Some(error) = self.main_window.take_save_error() {
+            self.report_save_error(Err(error));
+        }
+    }
+
+    /// Shows a failed save of the local state, the app keeps working with the state in memory
+    fn report_save_error(&mut self, result: Result<(), String>) {
+        if let Err(e) = result {
+            self.set_status(format!("{}: {}", self.strings().save_failed, e), true);
+        }
+    }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// The settings next to the main window borrowed mutably, so the UI doesn't have to copy the settings
+    pub fn settings_and_main_window_mut(&mut self) -> (&Settings, &mut MainWindow) {
+        (&self.settings, &mut self.main_window)
+    }
+
+    pub fn strings(&self) -> &'static Strings {
+        self.settings.language.strings()
+    }
+
+    /// Reloads the settings if the settings file has changed. Returns true if new settings were applied
+    pub fn reload_settings(&mut self) -> bool {
+        match self.settings_watcher.poll() {
+            Some(Ok(loaded)) => {
+                self.settings = loaded.settings;
+                self.main_window.chat_manager.set_folders(self.settings.folders.clone());
+                let mut status = vec![self.strings().settings_reloaded.to_string()];
+                status.extend(loaded.warnings);
+                self.set_status(status.join("; "), false);
+                true
+            }
+            Some(Err(errors)) => {
+                let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                self.set_status(format!("{}: {}", storage::get_settings_file_path().display(), errors.join("; ")), true);
+                false
+            }
+            None => false,
+        }
+    }
+
+    pub fn get_status(&self) -> Option<&Status> {
+        self.status.as_ref()
+    }
+
+    pub fn set_status(&mut self, text: String, is_error: bool) {
+        self.status = Some(Status {
+            text,
+            is_error,
+            created_at: Instant::now(),
+        });
+    }
 
     pub fn quit(&mut self) {
+        self.main_window.stash_draft();
         self.should_quit = true;
     }
 
@@ -72,7 +174,43 @@ impl App {
     }
 
     pub fn pass_input_to_active_entity(&mut self, key_event: KeyEvent) {
+        let search_input = self.main_window.get_search_input();
         self.get_active_input_entity().process_input(key_event);
+        self.filter_chats_if_changed(&search_input);
+    }
+
+    pub fn paste(&mut self, text: &str) {
+        let search_input = self.main_window.get_search_input();
+        self.get_active_input_entity().paste(text);
+        self.filter_chats_if_changed(&search_input);
+    }
+
+    fn filter_chats_if_changed(&mut self, search_input: &TextInput) {
+        let is_searching_users = matches!(self.active_window, Windows::Main)
+            && self.main_window.get_search_mode() == SearchMode::Users;
+        if is_searching_users && self.main_window.get_search_input() != *search_input {
+            self.filter_chats();
+        }
+    }
+
+    /// Filters the chats by the search input right away and schedules the user search on the server
+    fn filter_chats(&mut self) {
+        let query = helpers::input_to_string(&self.main_window.get_search_input());
+        if query.is_empty() || command::is_command(&query) {
+            self.search_requested_at = None;
+            self.main_window.chat_manager.clear_search_results();
+            return;
+        }
+        let current_user_id = self.user.as_ref().map(|user| user.id.as_str());
+        self.main_window.chat_manager.filter_chats(&query, current_user_id);
+        self.search_requested_at = Some(Instant::now());
+    }
+
+    /// Sends the scheduled user search once the search input hasn't changed for a while
+    pub async fn run_pending_search(&mut self) {
+        if self.search_requested_at.is_some_and(|requested_at| requested_at.elapsed() >= SEARCH_DEBOUNCE) {
+            self.run_search().await;
+        }
     }
 
     pub async fn submit(&mut self) {
@@ -90,25 +228,64 @@ impl App {
             Windows::Main => {
                 match self.main_window.get_active_input_entity() {
                     window::main::ActiveInputEntity::SearchChats => {
-                        self.run_search().await;
+                        let input = self.main_window.get_active_input();
+                        if command::is_command(&input) {
+                            self.run_command(&input);
+                            self.main_window.clear_search();
+                            return;
+                        }
+                        match self.main_window.get_search_mode() {
+                            SearchMode::Users => self.run_search().await,
+                            SearchMode::Messages => self.run_message_search(),
+                        }
                     }
                     window::main::ActiveInputEntity::SelectChat => {
-                        self.open_chat(
-                            self.main_window.chat_manager.get_selected_chat().expect("Cannot open chat without a selected chat")
-                        ).await;
+                        match self.main_window.get_search_mode() {
+                            SearchMode::Users => {
+                                self.open_chat(
+                                    self.main_window.chat_manager.get_selected_chat().expect("Cannot open chat without a selected chat")
+                                ).await;
+                            }
+                            SearchMode::Messages => {
+                                if let Some(hit) = self.main_window.chat_manager.get_selected_message_search_hit() {
+                                    self.open_message_search_hit(hit).await;
+                                }
+                            }
+                        }
+                    }
+                    window::main::ActiveInputEntity::SearchMessages => {
+                        self.main_window.select_chat_search_match(true);
+                    }
+                    window::main::ActiveInputEntity::SearchSentMessages => {
+                        self.main_window.accept_sent_message_search();
+                    }
+                    window::main::ActiveInputEntity::CommandLine => {
+                        let input = self.main_window.get_active_input();
+                        self.main_window.close_command_line();
+                        if !input.trim().is_empty() {
+                            self.run_command(&input);
+                        }
                     }
                     window::main::ActiveInputEntity::EnterMessage => {
+                        if self.main_window.needs_send_confirmation() {
+                            return;
+                        }
+                        // the message goes to the open chat, the selection in the list can move away from it
+                        let Some(chat) = self.main_window.chat_manager.get_loaded_chat().cloned() else {
+                            return;
+                        };
                         let message_str = helpers::input_to_string(&self.main_window.pop_message_input());
                         if message_str.is_empty() {
                             return;
                         }
                         // todo new chats do not have id.. will it contain None for new chats?
-                        let chat = self.main_window.chat_manager.get_selected_chat().unwrap();
-                        if chat.id.is_some() {
+                        self.main_window.follow_newest_message();
+                        if let Some(chat_id) = chat.id {
                             let message = NewMessage {
-                                chat_id: chat.id.unwrap(),
+                                chat_id,
                                 text: message_str,
                                 sender_id: self.user.as_ref().unwrap().id.clone(),
+                                client_message_id: helpers::generate_client_message_id(),
                             };
                             self.send_message(message).await;
                         } else {
@@ -127,35 +304,80 @@ impl App {
 
     async fn open_chat(&mut self, chat: Chat) {
         let mut chat_id = None;
-        if chat.id.is_some() {
-            self.api_client.mark_chat_as_read(chat.id.unwrap()).await;
-            self.main_window.chat_manager.load_chat(chat.internal_id.to_string());
-            chat_id = Some(chat.id.unwrap().to_string());
-        } else {
-            // when searching for users they are shown as not existing chats
-            // so if there IS a chat with this user, we should use its internal_id
-            // it is a dirty temporary solution
-            if let Some(existing_chat) = self.main_window.chat_manager.get_chat_by_name(&chat.name) {
-                self.main_window.chat_manager.load_specifically_chat(
-                    existing_chat.internal_id.to_string()
-                );
-                self.api_client.mark_chat_as_read(existing_chat.id.unwrap()).await;
-                chat_id = Some(existing_chat.id.unwrap().to_string());
-            } else {
-                self.main_window.chat_manager.load_chat(
-                    chat.internal_id.to_string()
-                );
-            };
-        };
+        let mut unread_count = 0;
+        self.search_requested_at = None;
+        self.main_window.stash_draft();
+        self.main_window.reset_message_view();
+        if let Some(id) = chat.id {
+            unread_count = self.main_window.chat_manager.get_chat(id).number_of_unread_messages;
+            self.api_client.mark_chat_as_read(id).await;
+            chat_id = Some(id.to_string());
+        }
+        // users found by the search are shown as not existing chats, they are loaded from the search results
+        self.main_window.chat_manager.load_chat(chat.internal_id.to_string());
         self.main_window.set_active_input_entity(window::main::ActiveInputEntity::EnterMessage);
         if let Some(chat_id) = chat_id {
             self.main_window.chat_manager.clear_search_results();
             self.main_window.chat_manager.select_chat(chat_id);
         }
+        self.main_window.restore_draft();
+        self.main_window.show_first_unread(unread_count);
+    }
+
+    async fn open_message_search_hit(&mut self, hit: MessageSearchHit) {
+        let chat = self.main_window.chat_manager.get_chat(hit.chat_id).clone();
+        let query = self.main_window.get_search_input();
+        self.open_chat(chat).await;
+        self.main_window.focus_message(hit.message_index, tokenize(&helpers::input_to_string(&query)));
+    }
+
+    fn run_command(&mut self, input: &str) {
+        match command::parse(input, self.strings()) {
+            Ok(command) => self.execute_command(command),
+            Err(error) => self.set_status(error, true),
+        }
+    }
+
+    fn execute_command(&mut self, command: Command) {
+        match command {
+            Command::Quit => self.quit(),
+            Command::Export { format, all_chats, output } => {
+                let target = if all_chats {
+                    ExportTarget::AllChats
+                } else {
+                    let chat = self.main_window.chat_manager.get_loaded_chat().cloned()
+                        .or_else(|| self.main_window.chat_manager.get_selected_chat());
+                    match chat {
+                        Some(chat) => ExportTarget::Chat(Box::new(chat)),
+                        None => {
+                            self.set_status(self.strings().select_chat_to_export.to_string(), true);
+                            return;
+                        }
+                    }
+                };
+                let output = output.unwrap_or_else(storage::get_export_dir);
+                match export::export(&self.main_window.chat_manager, target, format, &output) {
+                    Ok(path) => self.set_status(format!("{} {}", self.strings().exported_to, path.display()), false),
+                    Err(e) => self.set_status(e.to_string(), true),
+                }
+            }
+        }
+    }
+
+    fn run_message_search(&mut self) {
+        let query = self.main_window.get_active_input();
+        if query.is_empty() {
+            self.main_window.chat_manager.clear_message_search_results();
+            return;
+        }
+
+        self.main_window.chat_manager.search_messages(&query);
     }
 
+    /// Searches users on the server and adds the ones without a direct chat to the locally filtered chats
     async fn run_search(&mut self) {
-        let name_like = self.main_window.get_active_input();
+        self.search_requested_at = None;
+        let name_like = helpers::input_to_string(&self.main_window.get_search_input());
         if name_like.is_empty() {
             self.main_window.chat_manager.clear_search_results();
             return;
@@ -165,20 +387,30 @@ impl App {
             Ok(user_search_results) => {
                 let mut chats = vec![];
                 for user in user_search_results.users {
+                    if self.main_window.chat_manager.has_direct_chat_with(&user.id) {
+                        continue;
+                    }
                     chats.push(Chat {
-                        internal_id: user.username.clone(),
+                        internal_id: format!("user:{}", user.id),
                         id: None,
                         name: user.username.clone(),
                         members: vec![user, self.user.as_ref().unwrap().clone()],
                         last_message: None,
                         number_of_unread_messages: 0,
+                        number_of_unread_mentions: 0,
+                        pin_position: None,
+                        is_archived: false,
+                        is_muted: false,
                     });
                 }
 
-                self.main_window.chat_manager.set_search_results(chats);
+                let current_user_id = self.user.as_ref().map(|user| user.id.as_str());
+                self.main_window.chat_manager.filter_chats(&name_like, current_user_id);
+                self.main_window.chat_manager.add_search_results(chats);
             }
-            Err(ApiError::Unauthenticated) => return,
-            Err(e) => panic!("Error while searching for users: {:?}", e),
+            Err(ApiError::Unauthenticated) => {}
+            // the search runs while the user types, the local filter results stay
+            Err(e) => self.set_status(format!("{}: {}", self.strings().user_search_failed, e), true),
         }
     }
 
@@ -191,7 +423,7 @@ impl App {
             if !self.main_window.chat_manager.has_chat(&message.chat_id) {
                 match self.api_client.get_chat(message.chat_id).await {
                     Ok(chat_model) => {
-                        App::save_new_users_data(&mut self.api_client, &mut self.chat_builder, &vec![chat_model.clone()]).await;
+                        App::save_new_users_data(&mut self.api_client, &mut self.chat_builder, std::slice::from_ref(&chat_model)).await;
                         self.main_window.chat_manager.add_chat(self.chat_builder.build_chat_from_model(chat_model));
                     }
                     Err(ApiError::Unauthenticated) => {
@@ -200,7 +432,26 @@ impl App {
                     Err(e) => panic!("Error while loading chat: {:?}", e),
                 }
             }
-            self.main_window.chat_manager.add_message(self.chat_builder.build_message_from_model(message));
+            let message = self.chat_builder.build_message_from_model(message);
+            let chat_manager = &self.main_window.chat_manager;
+            let is_loaded = chat_manager.get_loaded_chat().is_some_and(|chat| chat.id == Some(message.chat_id));
+            // mentions notify even in muted chats
+            let should_notify = (!chat_manager.is_muted(message.chat_id) || message.mentions_current_user)
+                && !is_loaded
+                && self.user.as_ref().is_some_and(|user| user.id != message.sender_id);
+
+            let message_sender_id = message.sender_id.clone();
+            let unarchived = self.main_window.chat_manager.add_message(message);
+            if is_loaded && self.user.as_ref().is_some_and(|user| user.id != message_sender_id) {
+                self.main_window.note_new_message();
+            }
+            if should_notify && self.settings.bell {
+                helpers::ring_bell();
+            }
+            if unarchived {
+                let result = storage::store_archived_chats(self.main_window.chat_manager.get_archived_chat_ids());
+                self.report_save_error(result);
+            }
         }
     }
 
@@ -209,7 +460,7 @@ impl App {
         let res = self.login_window.get_input_values();
 
         if res["username"].is_empty() || res["password"].is_empty() {
-            self.login_window.login_error_message = "Username and password are required.".to_string();
+            self.login_window.login_error_message = self.strings().username_and_password_required.to_string();
             return;
         }
         let username = helpers::input_to_string(&res["username"]);
@@ -225,18 +476,13 @@ impl App {
                 self.user = Some(user);
 
                 // todo it's duplicate with new()
-                let (chat_models, messages) = Self::load_chats_and_messages(&mut self.api_client).await;
-                let user_ids = extract_user_ids(&chat_models);
-                if !user_ids.is_empty() {
-                    match self.api_client.get_users_by_ids(user_ids).await {
-                        Ok(users_result) => {
-                            self.chat_builder = factory::get_chat_builder(users_result.users, get_current_user());
-                            self.main_window.chat_manager.add_chats(self.chat_builder.build_chats_from_models(chat_models));
-                            self.main_window.chat_manager.add_messages(self.chat_builder.build_messages_from_models(messages));
-                        }
-                        Err(e) => panic!("Error while loading users: {:?}", e),
-                    }
-                }
+                let pinned_chat_ids;
+                (pinned_chat_ids, self.pins_on_server) = Self::load_pinned_chats(&mut self.api_client).await;
+                self.main_window.chat_manager.set_pinned_chat_ids(pinned_chat_ids);
+                self.main_window.chat_manager.set_archived_chat_ids(storage::load_archived_chats());
+                self.main_window.chat_manager.set_mutes(storage::load_muted_chats());
+                self.chat_builder = factory::get_chat_builder(vec![], get_current_user());
+                App::load_chats(&mut self.api_client, &mut self.chat_builder, &mut self.main_window.chat_manager).await;
                 self.active_window = Windows::Main;
             }
             Err(e) => {
@@ -277,33 +523,65 @@ impl App {
         let mut error_message = String::new();
 
         if input_values["password"] != input_values["password_confirmation"] {
-            error_message.push_str("Passwords do not match.\n");
+            error_message.push_str(self.strings().passwords_do_not_match);
+            error_message.push('\n');
             return Some(error_message);
         }
 
         if input_values["password"].len() < 8 {
-            error_message.push_str("Password must be at least 8 characters long.\n");
+            error_message.push_str(self.strings().password_too_short);
+            error_message.push('\n');
             return Some(error_message);
         }
 
         if input_values["username"].len() < 3 {
-            error_message.push_str("Username must be at least 3 characters long.\n");
+            error_message.push_str(self.strings().username_too_short);
+            error_message.push('\n');
             return Some(error_message);
         }
 
         None
     }
 
+    /// Shows the message right away as pending and sends it, it's confirmed when the server echoes it back
     async fn send_message(&mut self, message: NewMessage) {
-        self.api_client.send_message(message).await;
+        let pending_message = self.chat_builder.build_pending_message(&message);
+        self.main_window.chat_manager.add_pending_message(pending_message);
+        self.deliver_message(message).await;
+    }
+
+    async fn deliver_message(&mut self, message: NewMessage) {
+        let (chat_id, client_message_id) = (message.chat_id, message.client_message_id.clone());
+        if self.api_client.send_message(message).await.is_err() {
+            self.main_window.chat_manager.set_delivery_state(chat_id, &client_message_id, DeliveryState::Failed);
+        }
+    }
+
+    /// Sends the failed messages of the loaded chat again
+    pub async fn retry_failed_messages(&mut self) {
+        let Some(chat_id) = self.main_window.chat_manager.get_loaded_chat().and_then(|chat| chat.id) else {
+            return;
+        };
+        for failed_message in self.main_window.chat_manager.get_failed_messages(chat_id) {
+            let Some(client_message_id) = failed_message.client_message_id else {
+                continue;
+            };
+            self.main_window.chat_manager.set_delivery_state(chat_id, &client_message_id, DeliveryState::Pending(Instant::now()));
+            self.deliver_message(NewMessage {
+                chat_id,
+                sender_id: failed_message.sender_id,
+                text: failed_message.text,
+                client_message_id,
+            }).await;
+        }
     }
 
     async fn create_chat(&mut self, chat: NewChatModel) {
         match self.api_client.create_chat(chat).await {
             Ok(chat_model) => {
-                let chat_id = chat_model.id.clone();
+                let chat_id = chat_model.id;
 
-                App::save_new_users_data(&mut self.api_client, &mut self.chat_builder, &vec![chat_model.clone()]).await;
+                App::save_new_users_data(&mut self.api_client, &mut self.chat_builder, std::slice::from_ref(&chat_model)).await;
 
                 // order is important: first clear search, then select chat
                 self.main_window.chat_manager.clear_search_results();
@@ -312,28 +590,145 @@ impl App {
                 self.main_window.chat_manager.select_chat(chat_id.to_string());
                 self.main_window.chat_manager.load_chat(chat_id.to_string());
             }
-            Err(ApiError::Unauthenticated) => return,
+            Err(ApiError::Unauthenticated) => {}
             Err(e) => panic!("Error while creating chat: {:?}", e),
         }
     }
 
-    async fn save_new_users_data(api_client: &mut api::Client, chat_builder: &mut ChatBuilder, chat_models: &Vec<ChatModel>) {
-        if chat_models.is_empty() {
-            return;
-        }
-        
+    /// Prefetches the members of the chats so that they are shown without placeholders.
+    /// Users that fail to load here are fetched lazily by `resolve_users`
+    async fn save_new_users_data(api_client: &mut api::Client, chat_builder: &mut ChatBuilder, chat_models: &[ChatModel]) {
         let user_ids = extract_user_ids(chat_models);
         if user_ids.is_empty() {
-            panic!("No user ids found in chat model");
+            return;
+        }
+        if let Ok(users_result) = api_client.get_users_by_ids(user_ids).await {
+            chat_builder.add_users(users_result.users);
+        }
+    }
+
+    /// Starts fetching the next batch of users the chat builder had to show placeholders for.
+    /// The result comes back as `Event::UsersFetched`, so the loop doesn't wait for the request
+    pub fn resolve_users(&mut self, sender: UnboundedSender<Event>) {
+        if !self.api_client.is_authenticated() {
+            return;
+        }
+        let Some(user_ids) = self.chat_builder.take_users_to_fetch() else {
+            return;
+        };
+        let request = self.api_client.get_users_by_ids_detached(user_ids.clone());
+        tokio::spawn(async move {
+            let users = request.await.ok().map(|users_result| users_result.users);
+            // the receiver is gone only when the app is quitting
+            let _ = sender.send(Event::UsersFetched(user_ids, users));
+        });
+    }
+
+    pub fn users_fetched(&mut self, user_ids: Vec<UserId>, users: Option<Vec<User>>) {
+        match users {
+            Some(users) => {
+                self.main_window.chat_manager.update_users(&users);
+                self.chat_builder.users_fetched(user_ids, users);
+            }
+            None => self.chat_builder.users_fetch_failed(user_ids),
         }
-        match api_client.get_users_by_ids(user_ids).await {
-            Ok(users_result) => {
-                chat_builder.add_users(users_result.users);
+    }
+
+    /// Puts the next older or newer message sent by the user in the loaded chat into the message input
+    pub fn recall_sent_message(&mut self, older: bool) {
+        let (Some(user), Some(chat_id)) = (&self.user, self.main_window.chat_manager.get_loaded_chat().and_then(|chat| chat.id)) else {
+            return;
+        };
+        let sent_messages = self.main_window.chat_manager.get_sent_messages(&user.id, Some(chat_id));
+        self.main_window.recall_sent_message(&sent_messages, older);
+    }
+
+    /// Starts the reverse search in the messages sent by the user in all chats
+    pub fn start_sent_message_search(&mut self) {
+        let Some(user) = &self.user else {
+            return;
+        };
+        let sent_messages = self.main_window.chat_manager.get_sent_messages(&user.id, None);
+        self.main_window.start_sent_message_search(sent_messages);
+    }
+
+    pub async fn toggle_selected_chat_pin(&mut self) {
+        let Some(chat_id) = self.main_window.chat_manager.get_selected_chat().and_then(|chat| chat.id) else {
+            return;
+        };
+        self.main_window.chat_manager.toggle_pin(chat_id);
+        self.main_window.chat_manager.select_chat(chat_id.to_string());
+        self.save_pinned_chats().await;
+    }
+
+    pub async fn move_selected_chat_pin(&mut self, up: bool) {
+        let Some(chat_id) = self.main_window.chat_manager.get_selected_chat().and_then(|chat| chat.id) else {
+            return;
+        };
+        if self.main_window.chat_manager.move_pin(chat_id, up) {
+            self.save_pinned_chats().await;
+        }
+    }
+
+    pub fn toggle_selected_chat_archive(&mut self) {
+        let Some(chat_id) = self.main_window.chat_manager.get_selected_chat().and_then(|chat| chat.id) else {
+            return;
+        };
+        self.main_window.chat_manager.toggle_archive(chat_id);
+        let result = storage::store_archived_chats(self.main_window.chat_manager.get_archived_chat_ids());
+        self.report_save_error(result);
+    }
+
+    pub fn cycle_selected_chat_mute(&mut self) {
+        let Some(chat_id) = self.main_window.chat_manager.get_selected_chat().and_then(|chat| chat.id) else {
+            return;
+        };
+        let duration = self.main_window.chat_manager.cycle_mute(chat_id);
+        let result = storage::store_muted_chats(self.main_window.chat_manager.get_mutes());
+
+        let strings = self.strings();
+        let status = match duration {
+            Some(MuteDuration::OneHour) => strings.muted_for_hour,
+            Some(MuteDuration::EightHours) => strings.muted_for_eight_hours,
+            Some(MuteDuration::Forever) => strings.muted_forever,
+            None => strings.unmuted,
+        };
+        self.set_status(status.to_string(), false);
+        self.report_save_error(result);
+    }
+
+    async fn save_pinned_chats(&mut self) {
+        let chat_ids = self.main_window.chat_manager.get_pinned_chat_ids().clone();
+        let result = storage::store_pinned_chats(&chat_ids);
+        self.report_save_error(result);
+        if self.pins_on_server {
+            if let Err(ApiError::NotFound(_)) = self.api_client.set_pinned_chats(&PinnedChats { chat_ids }).await {
+                self.pins_on_server = false;
+            }
+        }
+    }
+
+    /// Loads the pinned chats from the server, or from the local storage if the server doesn't support them
+    /// or can't be reached. Returns the pinned chat ids and whether the pins are kept on the server
+    async fn load_pinned_chats(api_client: &mut api::Client) -> (Vec<ChatId>, bool) {
+        match api_client.get_pinned_chats().await {
+            Ok(pinned_chats) => {
+                // the local copy is only a fallback for when the server is unreachable, the server copy is used now
+                storage::store_pinned_chats(&pinned_chats.chat_ids).ok();
+                (pinned_chats.chat_ids, true)
             }
-            Err(e) => panic!("Error while loading users: {:?}", e),
+            Err(_) => (storage::load_pinned_chats(), false),
         }
     }
 
+    /// Loads the chats of the user with their messages and members into the chat manager
+    pub async fn load_chats(api_client: &mut api::Client, chat_builder: &mut ChatBuilder, chat_manager: &mut ChatManager) {
+        let (chat_models, messages) = Self::load_chats_and_messages(api_client).await;
+        App::save_new_users_data(api_client, chat_builder, &chat_models).await;
+        chat_manager.add_chats(chat_builder.build_chats_from_models(chat_models));
+        chat_manager.add_messages(chat_builder.build_messages_from_models(messages));
+    }
+
     async fn load_chats_and_messages(api_client: &mut api::Client) -> (Vec<ChatModel>, HashMap<ChatId, Vec<MessageModel>>) {
         match api_client.get_chats().await {
             Ok(chat_results) => {
@@ -365,7 +760,7 @@ pub fn get_current_user() -> Option<User> {
     storage::load_user()
 }
 
-fn extract_user_ids(chats: &Vec<ChatModel>) -> Vec<String> {
+fn extract_user_ids(chats: &[ChatModel]) -> Vec<String> {
     let mut user_ids = HashSet::new();
     for chat in chats {
         for member_id in chat.member_ids.iter() {
@@ -1,51 +1,49 @@
 use std::collections::HashMap;
-use crate::chat::{Chat, Message};
+use std::time::Instant;
+use crate::chat::{mentions, Chat, DeliveryState, Message};
+use crate::chat::directory::UserDirectory;
 use crate::helpers::types::{ChatId, UserId};
-use crate::schemas::{ChatModel, MessageModel, User};
-
-pub struct UserProvider {
-    users: HashMap<UserId, User>,
-}
-
-impl UserProvider {
-    pub fn new(users: Vec<User>) -> Self {
-        let users = users.into_iter().map(|user| (user.id.clone(), user)).collect();
-        UserProvider { users }
-    }
-
-    pub fn get_user(&self, user_id: &str) -> User {
-        self.users.get(user_id).unwrap().clone()
-    }
-}
+use crate::helpers;
+use crate::schemas::{ChatModel, MessageModel, NewMessage, User};
 
 pub struct ChatBuilder {
     current_user: Option<User>,
-    user_provider: UserProvider,
+    user_directory: UserDirectory,
 }
 
 impl ChatBuilder {
-    pub fn new(current_user: Option<User>, username_provider: UserProvider) -> Self {
+    pub fn new(current_user: Option<User>, user_directory: UserDirectory) -> Self {
         ChatBuilder {
             current_user,
-            user_provider: username_provider,
+            user_directory,
         }
     }
     
     pub fn add_users(&mut self, user: Vec<User>) {
-        for user in user {
-            self.user_provider.users.insert(user.id.clone(), user);
-        }
+        self.user_directory.add_users(user);
+    }
+
+    pub fn take_users_to_fetch(&mut self) -> Option<Vec<UserId>> {
+        self.user_directory.take_due_batch()
+    }
+
+    pub fn users_fetched(&mut self, requested: Vec<UserId>, users: Vec<User>) {
+        self.user_directory.fetch_succeeded(requested, users);
+    }
+
+    pub fn users_fetch_failed(&mut self, requested: Vec<UserId>) {
+        self.user_directory.fetch_failed(requested);
     }
     
-    pub fn build_chats_from_models(&self, chat_models: Vec<ChatModel>) -> Vec<Chat> {
+    pub fn build_chats_from_models(&mut self, chat_models: Vec<ChatModel>) -> Vec<Chat> {
         chat_models.into_iter().map(|chat_model| self.build_chat_from_model(chat_model)).collect()
     }
 
-    pub fn build_chat_from_model(&self, chat_model: ChatModel) -> Chat {
+    pub fn build_chat_from_model(&mut self, chat_model: ChatModel) -> Chat {
         let members = chat_model
             .member_ids
             .iter()
-            .map(|user_id| self.user_provider.get_user(user_id))
+            .map(|user_id| self.user_directory.get_user(user_id))
             .collect();
        
         Chat {
@@ -55,10 +53,14 @@ impl ChatBuilder {
             members,
             last_message: chat_model.messages.last().map(|message_model| self.build_message_from_model(message_model.clone())),
             number_of_unread_messages: 0,
+            number_of_unread_mentions: 0,
+            pin_position: None,
+            is_archived: false,
+            is_muted: false,
         }
     }
 
-    pub fn build_messages_from_models(&self, message_models: HashMap<ChatId, Vec<MessageModel>>) -> HashMap<ChatId, Vec<Message>> {
+    pub fn build_messages_from_models(&mut self, message_models: HashMap<ChatId, Vec<MessageModel>>) -> HashMap<ChatId, Vec<Message>> {
         message_models
             .into_iter()
             .map(|(chat_id, messages)|
@@ -67,33 +69,59 @@ impl ChatBuilder {
             .collect()
     }
 
-    pub fn build_message_from_model(&self, message_model: MessageModel) -> Message {
+    pub fn build_message_from_model(&mut self, message_model: MessageModel) -> Message {
         Message {
+            mentions_current_user: self.mentions_current_user(&message_model.text),
             chat_id: message_model.chat_id,
-            sender_username: self.user_provider.get_user(&message_model.sender_id).username,
-            text: message_model.text.clone(),
+            sender_username: self.user_directory.get_user(&message_model.sender_id).username,
+            sender_id: message_model.sender_id,
+            text: message_model.text,
             created_at: message_model.created_at,
             is_read: message_model.is_read,
+            attachments: message_model.attachments,
+            client_message_id: message_model.client_message_id,
+            delivery_state: DeliveryState::Sent,
         }
     }
 
-    fn get_chat_name(&self, chat_model: &ChatModel) -> String {
-        // If the chat has a name, return it. Otherwise, return the other member's username
-        // Group chats will always have a name
-        
+    /// Local copy of a message being sent, shown until the server echoes it back
+    pub fn build_pending_message(&mut self, new_message: &NewMessage) -> Message {
+        Message {
+            chat_id: new_message.chat_id,
+            sender_username: self.user_directory.get_user(&new_message.sender_id).username,
+            sender_id: new_message.sender_id.clone(),
+            text: new_message.text.clone(),
+            created_at: helpers::now_timestamp(),
+            is_read: true,
+            attachments: vec![],
+            client_message_id: Some(new_message.client_message_id.clone()),
+            delivery_state: DeliveryState::Pending(Instant::now()),
+            mentions_current_user: self.mentions_current_user(&new_message.text),
+        }
+    }
 
-        return if let Some(name) = chat_model.name.as_ref() {
+    fn mentions_current_user(&self, text: &str) -> bool {
+        self.current_user.as_ref().is_some_and(|user| mentions(text, &user.username))
+    }
+
+    fn get_chat_name(&mut self, chat_model: &ChatModel) -> String {
+        // If the chat has a name, return it. Otherwise, return the other members' usernames
+        // Group chats will always have a name
+        if let Some(name) = chat_model.name.as_ref() {
             name.to_string()
         } else {
-            assert_eq!(chat_model.member_ids.len(), 2, "Chat name is None and chat has more or less than 2 members");
-
-            let another_user_id = chat_model
+            let current_user_id = self.current_user.as_ref().map(|user| user.id.clone());
+            let other_user_ids: Vec<&String> = chat_model
                 .member_ids
                 .iter()
-                .find(|other_user_id| *other_user_id != &self.current_user.as_ref().expect("Cannot load usernames when unauthenticated").id)
-                .unwrap();
+                .filter(|user_id| Some(*user_id) != current_user_id.as_ref())
+                .collect();
 
-            self.user_provider.get_user(another_user_id).username.clone()
-        };
+            other_user_ids
+                .into_iter()
+                .map(|user_id| self.user_directory.get_user(user_id).username)
+                .collect::<Vec<String>>()
+                .join(", ")
+        }
     }
 }
@@ -0,0 +1,169 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+use crate::helpers::types::UserId;
+use crate::schemas::User;
+
+const USER_TTL: Duration = Duration::from_secs(10 * 60);
+const FETCH_RETRY_DELAY: Duration = Duration::from_secs(5);
+const MAX_BATCH_SIZE: usize = 100;
+
+struct CachedUser {
+    /// None if the server doesn't know this user
+    user: Option<User>,
+    fetched_at: Instant,
+}
+
+/// Cache of known users. Unknown users get a placeholder and are queued
+/// to be fetched from the server in batches, see `take_due_batch`
+pub struct UserDirectory {
+    users: HashMap<UserId, CachedUser>,
+    pending: HashSet<UserId>,
+    next_fetch_at: Instant,
+}
+
+impl UserDirectory {
+    pub fn new(users: Vec<User>) -> Self {
+        let mut directory = UserDirectory {
+            users: HashMap::new(),
+            pending: HashSet::new(),
+            next_fetch_at: Instant::now(),
+        };
+        directory.add_users(users);
+        directory
+    }
+
+    pub fn add_users(&mut self, users: Vec<User>) {
+        let now = Instant::now();
+        for user in users {
+            self.pending.remove(&user.id);
+            self.users.insert(user.id.clone(), CachedUser { user: Some(user), fetched_at: now });
+        }
+    }
+
+    /// Returns the user or a placeholder if the user is not known yet. Missing and
+    /// expired users are queued to be fetched
+    pub fn get_user(&mut self, user_id: &str) -> User {
+        match self.users.get(user_id) {
+            Some(cached) => {
+                if cached.fetched_at.elapsed() > USER_TTL {
+                    self.pending.insert(user_id.to_string());
+                }
+                cached.user.clone().unwrap_or_else(|| placeholder_user(user_id))
+            }
+            None => {
+                self.pending.insert(user_id.to_string());
+                placeholder_user(user_id)
+            }
+        }
+    }
+
+    /// Takes the next batch of user ids to fetch, if any are waiting and the retry delay has passed
+    pub fn take_due_batch(&mut self) -> Option<Vec<UserId>> {
+        if self.pending.is_empty() || Instant::now() < self.next_fetch_at {
+            return None;
+        }
+        let batch: Vec<UserId> = self.pending.iter().take(MAX_BATCH_SIZE).cloned().collect();
+        for user_id in batch.iter() {
+            self.pending.remove(user_id);
+        }
+        Some(batch)
+    }
+
+    /// Stores the fetched users. Requested users missing from the response are remembered as unknown
+    pub fn fetch_succeeded(&mut self, requested: Vec<UserId>, users: Vec<User>) {
+        let now = Instant::now();
+        let found: HashSet<&UserId> = users.iter().map(|user| &user.id).collect();
+        for user_id in requested.into_iter().filter(|user_id| !found.contains(user_id)) {
+            self.users.insert(user_id, CachedUser { user: None, fetched_at: now });
+        }
+        self.add_users(users);
+    }
+
+    pub fn fetch_failed(&mut self, requested: Vec<UserId>) {
+        self.pending.extend(requested);
+        self.next_fetch_at = Instant::now() + FETCH_RETRY_DELAY;
+    }
+}
+
+/// Display name used until the user is fetched
+pub fn placeholder_name(user_id: &str) -> String {
+    format!("user-{}", user_id.chars().take(8).collect::<String>())
+}
+
+fn placeholder_user(user_id: &str) -> User {
+    User {
+        id: user_id.to_string(),
+        username: placeholder_name(user_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(id: &str) -> User {
+        User { id: id.to_string(), username: format!("name-{}", id) }
+    }
+
+    fn sorted(mut user_ids: Vec<UserId>) -> Vec<UserId> {
+        user_ids.sort();
+        user_ids
+    }
+
+    #[test]
+    fn test_unknown_users_are_fetched_in_batches() {
+        let mut directory = UserDirectory::new(vec![user("known")]);
+        assert_eq!(directory.get_user("known").username, "name-known");
+        assert!(directory.take_due_batch().is_none());
+
+        for i in 0..MAX_BATCH_SIZE + 5 {
+            let user_id = format!("unknown-{}", i);
+            assert_eq!(directory.get_user(&user_id).username, placeholder_name(&user_id));
+        }
+        // asking again doesn't queue the user twice
+        directory.get_user("unknown-0");
+        assert_eq!(directory.take_due_batch().map(|batch| batch.len()), Some(MAX_BATCH_SIZE));
+        assert_eq!(directory.take_due_batch().map(|batch| batch.len()), Some(5));
+        assert!(directory.take_due_batch().is_none());
+    }
+
+    #[test]
+    fn test_fetched_and_missing_users_are_cached() {
+        let mut directory = UserDirectory::new(vec![]);
+        directory.get_user("a");
+        directory.get_user("b");
+        let batch = sorted(directory.take_due_batch().unwrap());
+        assert_eq!(batch, vec!["a".to_string(), "b".to_string()]);
+
+        directory.fetch_succeeded(batch, vec![user("a")]);
+        assert_eq!(directory.get_user("a").username, "name-a");
+        // the server doesn't know `b`, it keeps the placeholder without being fetched again
+        assert_eq!(directory.get_user("b").username, placeholder_name("b"));
+        assert!(directory.take_due_batch().is_none());
+    }
+
+    #[test]
+    fn test_failed_fetch_is_retried_after_a_delay() {
+        let mut directory = UserDirectory::new(vec![]);
+        directory.get_user("a");
+        let batch = directory.take_due_batch().unwrap();
+        directory.fetch_failed(batch);
+        assert!(directory.take_due_batch().is_none());
+
+        directory.next_fetch_at = Instant::now();
+        assert_eq!(directory.take_due_batch(), Some(vec!["a".to_string()]));
+    }
+
+    #[test]
+    fn test_expired_users_are_fetched_again() {
+        let mut directory = UserDirectory::new(vec![user("a")]);
+        directory.get_user("a");
+        assert!(directory.take_due_batch().is_none());
+
+        let expired_at = Instant::now().checked_sub(USER_TTL + Duration::from_secs(1)).unwrap();
+        directory.users.get_mut("a").unwrap().fetched_at = expired_at;
+        // the cached user is still shown until the new one arrives
+        assert_eq!(directory.get_user("a").username, "name-a");
+        assert_eq!(directory.take_due_batch(), Some(vec!["a".to_string()]));
+    }
+}
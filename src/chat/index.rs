@@ -0,0 +1,118 @@
+use std::collections::{BTreeMap, HashSet};
+use crate::helpers::types::ChatId;
+
+/// Position of a message in the `ChatManager` message cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MessageRef {
+    pub chat_id: ChatId,
+    pub index: usize,
+}
+
+/// Inverted index over the cached message history. Tokens are kept in a BTreeMap
+/// so that the last word of a query can be matched as a prefix while the user is still typing it
+#[derive(Default)]
+pub struct MessageIndex {
+    tokens: BTreeMap<String, HashSet<MessageRef>>,
+}
+
+impl MessageIndex {
+    pub fn add(&mut self, message_ref: MessageRef, text: &str) {
+        for token in tokenize(text) {
+            self.tokens.entry(token).or_default().insert(message_ref);
+        }
+    }
+
+    /// Removes the messages of the chat, used when its message history is loaded again
+    pub fn remove_chat(&mut self, chat_id: ChatId) {
+        self.tokens.retain(|_, refs| {
+            refs.retain(|message_ref| message_ref.chat_id != chat_id);
+            !refs.is_empty()
+        });
+    }
+
+    pub fn search(&self, query: &str) -> Vec<MessageRef> {
+        let terms = tokenize(query);
+        if terms.is_empty() {
+            return vec![];
+        }
+
+        let mut result: Option<HashSet<MessageRef>> = None;
+        for (i, term) in terms.iter().enumerate() {
+            let is_last = i == terms.len() - 1;
+            let matches = if is_last {
+                self.prefix_matches(term)
+            } else {
+                self.tokens.get(term).cloned().unwrap_or_default()
+            };
+            result = Some(match result {
+                None => matches,
+                Some(found) => found.intersection(&matches).copied().collect(),
+            });
+        }
+
+        result.unwrap_or_default().into_iter().collect()
+    }
+
+    fn prefix_matches(&self, prefix: &str) -> HashSet<MessageRef> {
+        self.tokens
+            .range(prefix.to_string()..)
+            .take_while(|(token, _)| token.starts_with(prefix))
+            .flat_map(|(_, refs)| refs.iter().copied())
+            .collect()
+    }
+}
+
+pub fn tokenize(text: &str) -> Vec<String> {
+    text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message_ref(chat_id: ChatId, index: usize) -> MessageRef {
+        MessageRef { chat_id, index }
+    }
+
+    fn sorted(mut refs: Vec<MessageRef>) -> Vec<MessageRef> {
+        refs.sort_by_key(|message_ref| (message_ref.chat_id, message_ref.index));
+        refs
+    }
+
+    #[test]
+    fn test_search_matches_last_term_as_prefix() {
+        let mut index = MessageIndex::default();
+        index.add(message_ref(1, 0), "Hello world");
+        index.add(message_ref(1, 1), "Hello, wonderful day");
+        index.add(message_ref(2, 0), "Goodbye world");
+
+        assert_eq!(sorted(index.search("hello wo")), vec![message_ref(1, 0), message_ref(1, 1)]);
+        assert_eq!(sorted(index.search("WORLD")), vec![message_ref(1, 0), message_ref(2, 0)]);
+        assert_eq!(sorted(index.search("hell")), sorted(index.search("hello")));
+    }
+
+    #[test]
+    fn test_search_matches_other_terms_exactly() {
+        let mut index = MessageIndex::default();
+        index.add(message_ref(1, 0), "Hello world");
+
+        assert!(index.search("hell world").is_empty());
+        assert!(index.search("").is_empty());
+        assert!(index.search("  , ").is_empty());
+    }
+
+    #[test]
+    fn test_remove_chat() {
+        let mut index = MessageIndex::default();
+        index.add(message_ref(1, 0), "hello");
+        index.add(message_ref(2, 0), "hello there");
+        index.remove_chat(2);
+
+        assert_eq!(index.search("hello"), vec![message_ref(1, 0)]);
+        assert!(index.search("there").is_empty());
+    }
+}
@@ -1,13 +1,32 @@
-use std::collections::HashMap;
-use crate::chat::{Chat, Message};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use crate::chat::{Chat, ChatFilter, DeliveryState, Message, MessageSearchHit, Mute, MuteDuration};
+use crate::chat::directory::placeholder_name;
+use crate::chat::index::{MessageIndex, MessageRef};
+use crate::helpers;
 use crate::helpers::list::StatefulOrderedList;
 use crate::helpers::types::ChatId;
+use crate::schemas::User;
+use crate::settings::Folder;
 
 #[derive(Default)]
 pub struct ChatManager {
+    /// All chats, including the archived ones
     chats: StatefulOrderedList<Chat>,
+    /// Chats of the current view, derived from `chats` by `refresh_visible_chats`
+    visible_chats: StatefulOrderedList<Chat>,
+    show_archived: bool,
+    filter: ChatFilter,
+    folders: Vec<Folder>,
     messages: HashMap<ChatId, Vec<Message>>,
+    message_index: MessageIndex,
     search_results: StatefulOrderedList<Chat>,
+    /// Whether the search results are shown instead of the chats, even if nothing was found
+    is_searching: bool,
+    pinned_chat_ids: Vec<ChatId>,
+    archived_chat_ids: Vec<ChatId>,
+    mutes: HashMap<ChatId, Mute>,
+    message_search_results: StatefulOrderedList<MessageSearchHit>,
     loaded_internal_chat_id: Option<String>,
 }
 
@@ -22,20 +41,256 @@ impl ChatManager {
         &self.chats.items
     }
 
+    pub fn get_chat(&self, chat_id: ChatId) -> &Chat {
+        self.chats.get(&chat_id.to_string())
+    }
+
     pub fn has_chat(&self, chat_id: &ChatId) -> bool {
         self.chats.contains(&chat_id.to_string())
     }
 
-    pub fn add_chats(&mut self, chats: Vec<Chat>) {
-        for chat in chats.iter() {
-            self.messages.insert(chat.id.clone().expect("Chat id not found"), vec![]);
+    pub fn add_chats(&mut self, mut chats: Vec<Chat>) {
+        for chat in chats.iter_mut() {
+            let chat_id = chat.id.expect("Chat id not found");
+            self.reset_messages(chat_id);
+            self.apply_local_state(chat);
         }
         self.chats.extend(chats);
+        self.refresh_visible_chats();
     }
 
-    pub fn add_chat(&mut self, chat: Chat) {
-        self.messages.insert(chat.id.clone().expect("Chat id not found"), vec![]);
+    pub fn add_chat(&mut self, mut chat: Chat) {
+        let chat_id = chat.id.expect("Chat id not found");
+        self.reset_messages(chat_id);
+        self.apply_local_state(&mut chat);
         self.chats.push(chat);
+        self.refresh_visible_chats();
+    }
+
+    /// Sets the pin, archive and mute state of a chat from the chat ids kept by the manager
+    fn apply_local_state(&self, chat: &mut Chat) {
+        let Some(chat_id) = chat.id else {
+            return;
+        };
+        chat.pin_position = self.pinned_chat_ids.iter().position(|id| *id == chat_id);
+        chat.is_archived = self.archived_chat_ids.contains(&chat_id);
+        chat.is_muted = self.mutes.contains_key(&chat_id);
+    }
+
+    /// Starts the message history of the chat from scratch, its old messages are removed from the index
+    fn reset_messages(&mut self, chat_id: ChatId) {
+        self.messages.insert(chat_id, vec![]);
+        self.message_index.remove_chat(chat_id);
+    }
+
+    pub fn get_pinned_chat_ids(&self) -> &Vec<ChatId> {
+        &self.pinned_chat_ids
+    }
+
+    pub fn set_pinned_chat_ids(&mut self, pinned_chat_ids: Vec<ChatId>) {
+        self.pinned_chat_ids = pinned_chat_ids;
+        self.update_pin_positions();
+    }
+
+    /// Pins the chat at the end of the pinned chats or unpins it if it is pinned
+    pub fn toggle_pin(&mut self, chat_id: ChatId) {
+        if let Some(position) = self.pinned_chat_ids.iter().position(|id| *id == chat_id) {
+            self.pinned_chat_ids.remove(position);
+        } else {
+            self.pinned_chat_ids.push(chat_id);
+        }
+        self.update_pin_positions();
+    }
+
+    /// Moves a pinned chat one position up or down among the pinned chats
+    pub fn move_pin(&mut self, chat_id: ChatId, up: bool) -> bool {
+        let Some(position) = self.pinned_chat_ids.iter().position(|id| *id == chat_id) else {
+            return false;
+        };
+        let new_position = if up {
+            position.checked_sub(1)
+        } else {
+            Some(position + 1).filter(|p| *p < self.pinned_chat_ids.len())
+        };
+        let Some(new_position) = new_position else {
+            return false;
+        };
+        self.pinned_chat_ids.swap(position, new_position);
+        self.update_pin_positions();
+        true
+    }
+
+    fn update_pin_positions(&mut self) {
+        for chat in self.chats.items.iter_mut() {
+            chat.pin_position = chat.id.and_then(|chat_id| self.pinned_chat_ids.iter().position(|id| *id == chat_id));
+        }
+        self.chats.update_order();
+        self.refresh_visible_chats();
+    }
+
+    pub fn get_archived_chat_ids(&self) -> &Vec<ChatId> {
+        &self.archived_chat_ids
+    }
+
+    pub fn set_archived_chat_ids(&mut self, archived_chat_ids: Vec<ChatId>) {
+        self.archived_chat_ids = archived_chat_ids;
+        self.update_archived();
+    }
+
+    /// Archives the chat or unarchives it if it is archived
+    pub fn toggle_archive(&mut self, chat_id: ChatId) {
+        if let Some(position) = self.archived_chat_ids.iter().position(|id| *id == chat_id) {
+            self.archived_chat_ids.remove(position);
+        } else {
+            self.archived_chat_ids.push(chat_id);
+        }
+        self.update_archived();
+    }
+
+    fn update_archived(&mut self) {
+        for chat in self.chats.items.iter_mut() {
+            chat.is_archived = chat.id.is_some_and(|chat_id| self.archived_chat_ids.contains(&chat_id));
+        }
+        self.refresh_visible_chats();
+    }
+
+    pub fn get_mutes(&self) -> &HashMap<ChatId, Mute> {
+        &self.mutes
+    }
+
+    pub fn set_mutes(&mut self, mutes: HashMap<ChatId, Mute>) {
+        self.mutes = mutes;
+        self.expire_mutes();
+        self.update_mutes();
+    }
+
+    pub fn is_muted(&self, chat_id: ChatId) -> bool {
+        self.mutes.contains_key(&chat_id)
+    }
+
+    /// Switches the chat to the next mute duration: 1 hour, 8 hours, forever and unmuted again.
+    /// Returns the new duration, None if the chat is unmuted
+    pub fn cycle_mute(&mut self, chat_id: ChatId) -> Option<MuteDuration> {
+        let duration = MuteDuration::next(self.mutes.get(&chat_id).map(|mute| mute.duration));
+        match duration {
+            Some(duration) => self.mutes.insert(chat_id, Mute::new(duration, helpers::now_timestamp())),
+            None => self.mutes.remove(&chat_id),
+        };
+        self.update_mutes();
+        duration
+    }
+
+    /// Unmutes the chats whose mute has ended. Returns true if any chat was unmuted
+    pub fn expire_mutes(&mut self) -> bool {
+        let now = helpers::now_timestamp();
+        let count = self.mutes.len();
+        self.mutes.retain(|_, mute| !mute.is_expired(now));
+        if self.mutes.len() == count {
+            return false;
+        }
+        self.update_mutes();
+        true
+    }
+
+    fn update_mutes(&mut self) {
+        for chat in self.chats.items.iter_mut() {
+            chat.is_muted = chat.id.is_some_and(|chat_id| self.mutes.contains_key(&chat_id));
+        }
+        self.refresh_visible_chats();
+    }
+
+    /// Total number of unread messages in all chats except the muted ones
+    pub fn get_unread_total(&self) -> u32 {
+        self.chats
+            .items
+            .iter()
+            .filter(|chat| !chat.is_muted)
+            .map(|chat| chat.number_of_unread_messages)
+            .sum()
+    }
+
+    pub fn is_archived_view(&self) -> bool {
+        self.show_archived
+    }
+
+    /// Switches between the main chat list and the archived chats
+    pub fn toggle_archived_view(&mut self) {
+        self.show_archived = !self.show_archived;
+        self.visible_chats.unselect();
+        self.refresh_visible_chats();
+    }
+
+    pub fn get_archived_chats_count(&self) -> usize {
+        self.chats.items.iter().filter(|chat| chat.is_archived).count()
+    }
+
+    /// Built-in filters followed by the folders
+    pub fn get_filters(&self) -> Vec<ChatFilter> {
+        ChatFilter::BUILT_IN
+            .into_iter()
+            .chain((0..self.folders.len()).map(ChatFilter::Folder))
+            .collect()
+    }
+
+    pub fn get_filter(&self) -> ChatFilter {
+        self.filter
+    }
+
+    pub fn get_folders(&self) -> &Vec<Folder> {
+        &self.folders
+    }
+
+    /// Replaces the folders, the filter is reset if its folder is gone
+    pub fn set_folders(&mut self, folders: Vec<Folder>) {
+        self.folders = folders;
+        if matches!(self.filter, ChatFilter::Folder(index) if index >= self.folders.len()) {
+            self.filter = ChatFilter::All;
+        }
+        self.refresh_visible_chats();
+    }
+
+    pub fn select_next_filter(&mut self) {
+        let filters = self.get_filters();
+        let index = filters.iter().position(|filter| *filter == self.filter).unwrap_or(0);
+        self.set_filter(filters[(index + 1) % filters.len()]);
+    }
+
+    pub fn select_previous_filter(&mut self) {
+        let filters = self.get_filters();
+        let index = filters.iter().position(|filter| *filter == self.filter).unwrap_or(0);
+        self.set_filter(filters[(index + filters.len() - 1) % filters.len()]);
+    }
+
+    pub fn set_filter(&mut self, filter: ChatFilter) {
+        self.filter = filter;
+        self.refresh_visible_chats();
+    }
+
+    /// Unread messages in the chats of the current view matching the filter, muted chats excluded
+    pub fn get_filter_unread_total(&self, filter: ChatFilter) -> u32 {
+        self.chats
+            .items
+            .iter()
+            .filter(|chat| chat.is_archived == self.show_archived && !chat.is_muted)
+            .filter(|chat| filter.matches(chat, &self.folders))
+            .map(|chat| chat.number_of_unread_messages)
+            .sum()
+    }
+
+    /// Rebuilds the chats of the current view and filter, keeping the selection if the selected chat is still there.
+    /// The open chat stays in the list until it is closed, even if it is archived, unarchived or doesn't match the filter anymore
+    fn refresh_visible_chats(&mut self) {
+        self.visible_chats.items = self
+            .chats
+            .items
+            .iter()
+            .filter(|chat| {
+                self.is_loaded(chat)
+                    || (chat.is_archived == self.show_archived && self.filter.matches(chat, &self.folders))
+            })
+            .cloned()
+            .collect();
+        self.visible_chats.update_order();
     }
 
     pub fn add_messages(&mut self, messages: HashMap<ChatId, Vec<Message>>) {
@@ -43,40 +298,149 @@ impl ChatManager {
             let chat = self.chats.get_mut(&chat_id.to_string());
             // todo what if I read them right away? I mean if the chat is open
             chat.number_of_unread_messages += messages.iter().filter(|m| !m.is_read).count() as u32;
+            chat.number_of_unread_mentions += messages.iter().filter(|m| !m.is_read && m.mentions_current_user).count() as u32;
 
-            self.messages.get_mut(&chat_id).expect("Chat messages not found").extend(messages);
+            for message in messages {
+                self.push_message(message);
+            }
         }
+        self.refresh_visible_chats();
     }
 
-    pub fn add_message(&mut self, message: Message) {
-        let loaded_chat = self.get_loaded_chat().clone();
-        if loaded_chat.is_none() || loaded_chat.unwrap().id != message.chat_id.into() {
+    /// Adds a new message, an archived chat is unarchived by it unless the chat is muted.
+    /// A message echoed by the server replaces the pending message with the same client message id.
+    /// Returns true if the chat was unarchived
+    pub fn add_message(&mut self, message: Message) -> bool {
+        if self.replace_sent_message(&message) {
+            return false;
+        }
+
+        let is_loaded = self.get_loaded_chat().is_some_and(|chat| chat.id == Some(message.chat_id));
+        if !is_loaded {
             let chat = self.chats.get_mut(&message.chat_id.to_string());
-            chat.number_of_unread_messages += if message.is_read { 0 } else { 1 };
+            if !message.is_read {
+                chat.number_of_unread_messages += 1;
+                chat.number_of_unread_mentions += message.mentions_current_user as u32;
+            }
         }
 
-        self.messages.get_mut(&message.chat_id).expect("Chat messages not found").push(message.clone());
-        self.chats.get_mut(&message.chat_id.to_string()).last_message = Some(message.clone());
+        let chat_id = message.chat_id;
+        self.chats.get_mut(&chat_id.to_string()).last_message = Some(message.clone());
+        self.push_message(message);
         self.chats.update_order();
+
+        let unarchive = self.archived_chat_ids.contains(&chat_id) && !self.is_muted(chat_id);
+        if unarchive {
+            self.toggle_archive(chat_id);
+        } else {
+            self.refresh_visible_chats();
+        }
+        unarchive
     }
-    
-    pub fn load_chat(&mut self, chat_internal_id: String) {
-        self.loaded_internal_chat_id = Some(chat_internal_id.clone());
-        let chats = self.get_active_chats_mut();
-        if chats.contains(&chat_internal_id) {
-            let chat = chats.get_mut(&chat_internal_id);
-            chat.number_of_unread_messages = 0;
+
+    /// Shows a message of the current user right away, before the server confirms it
+    pub fn add_pending_message(&mut self, message: Message) {
+        let chat_id = message.chat_id;
+        self.chats.get_mut(&chat_id.to_string()).last_message = Some(message.clone());
+        self.push_message(message);
+        self.chats.update_order();
+        self.refresh_visible_chats();
+    }
+
+    /// Replaces the local copy of a message with the one from the server.
+    /// Returns false if there is no local copy, i.e. the message is new
+    fn replace_sent_message(&mut self, message: &Message) -> bool {
+        let Some(client_message_id) = message.client_message_id.as_ref() else {
+            return false;
+        };
+        let Some(messages) = self.messages.get_mut(&message.chat_id) else {
+            return false;
+        };
+        let Some(existing) = messages
+            .iter_mut()
+            .find(|existing| existing.client_message_id.as_ref() == Some(client_message_id)) else {
+            return false;
+        };
+        *existing = message.clone();
+
+        let chat = self.chats.get_mut(&message.chat_id.to_string());
+        if chat.last_message.as_ref().is_some_and(|last| last.client_message_id.as_ref() == Some(client_message_id)) {
+            chat.last_message = Some(message.clone());
+        }
+        self.chats.update_order();
+        self.refresh_visible_chats();
+        true
+    }
+
+    pub fn set_delivery_state(&mut self, chat_id: ChatId, client_message_id: &str, delivery_state: DeliveryState) {
+        let message = self
+            .messages
+            .get_mut(&chat_id)
+            .and_then(|messages| messages.iter_mut().find(|message| message.client_message_id.as_deref() == Some(client_message_id)));
+        if let Some(message) = message {
+            message.delivery_state = delivery_state;
+        }
+    }
+
+    /// Marks the messages that were not confirmed within the timeout as failed
+    pub fn fail_pending_messages(&mut self, timeout: Duration) {
+        for message in self.messages.values_mut().flatten() {
+            if matches!(message.delivery_state, DeliveryState::Pending(sent_at) if sent_at.elapsed() > timeout) {
+                message.delivery_state = DeliveryState::Failed;
+            }
+        }
+    }
+
+    pub fn get_failed_messages(&self, chat_id: ChatId) -> Vec<Message> {
+        self.get_messages(chat_id)
+            .iter()
+            .filter(|message| message.delivery_state == DeliveryState::Failed)
+            .cloned()
+            .collect()
+    }
+
+    /// Replaces placeholder and outdated user data in chats and messages
+    pub fn update_users(&mut self, users: &[User]) {
+        for user in users {
+            let placeholder = placeholder_name(&user.id);
+            for chat_list in [&mut self.chats, &mut self.search_results] {
+                for chat in chat_list.items.iter_mut() {
+                    let Some(member) = chat.members.iter_mut().find(|member| member.id == user.id) else {
+                        continue;
+                    };
+                    *member = user.clone();
+                    if chat.name.contains(&placeholder) {
+                        chat.name = chat.name.replace(&placeholder, &user.username);
+                    }
+                    if let Some(message) = chat.last_message.as_mut().filter(|message| message.sender_id == user.id) {
+                        message.sender_username = user.username.clone();
+                    }
+                }
+            }
+            for message in self.messages.values_mut().flatten().filter(|message| message.sender_id == user.id) {
+                message.sender_username = user.username.clone();
+            }
         }
+        self.refresh_visible_chats();
     }
 
-    /// This method loads the chat and ignores search results. It's a fast and dirty solution
-    /// because I don't want to spend much time on this right now
-    pub fn load_specifically_chat(&mut self, chat_internal_id: String) {
+    fn push_message(&mut self, message: Message) {
+        let messages = self.messages.get_mut(&message.chat_id).expect("Chat messages not found");
+        self.message_index.add(
+            MessageRef { chat_id: message.chat_id, index: messages.len() },
+            &message.text,
+        );
+        messages.push(message);
+    }
+    
+    pub fn load_chat(&mut self, chat_internal_id: String) {
         self.loaded_internal_chat_id = Some(chat_internal_id.clone());
         if self.chats.contains(&chat_internal_id) {
             let chat = self.chats.get_mut(&chat_internal_id);
             chat.number_of_unread_messages = 0;
+            chat.number_of_unread_mentions = 0;
         }
+        self.refresh_visible_chats();
     }
 
     pub fn select_chat(&mut self, chat_id: String) {
@@ -91,43 +455,51 @@ impl ChatManager {
 
     pub fn get_selected_chat(&self) -> Option<Chat> {
         let chats = self.get_active_chats();
-        if chats.selected_item_id.is_none() {
-            return None;
-        }
-
-        let chat_id = chats.selected_item_id.as_ref().expect("Chat not found");
+        let chat_id = chats.selected_item_id.as_ref()?;
         Some(chats.get(chat_id).clone())
     }
 
     pub fn get_loaded_chat(&self) -> Option<&Chat> {
-        let chats = self.get_active_chats();
-        if let Some(chat_id) = &self.loaded_internal_chat_id {
-            return Some(chats.get(&chat_id));
+        let chat_id = self.loaded_internal_chat_id.as_ref()?;
+        if self.chats.contains(chat_id) {
+            return Some(self.chats.get(chat_id));
+        }
+        if self.search_results.contains(chat_id) {
+            return Some(self.search_results.get(chat_id));
         }
         None
     }
 
-    pub fn get_chat_by_name(&self, name: &str) -> Option<Chat> {
-        self.chats.items.iter().find(|chat| chat.name == name).cloned()
+    /// Whether there is a chat of only the current user and the given user
+    pub fn has_direct_chat_with(&self, user_id: &str) -> bool {
+        self.chats
+            .items
+            .iter()
+            .any(|chat| chat.members.len() == 2 && chat.members.iter().any(|member| member.id == user_id))
     }
 
     pub fn unload_chat(&mut self) {
         self.loaded_internal_chat_id = None;
+        self.refresh_visible_chats();
+    }
+
+    fn is_loaded(&self, chat: &Chat) -> bool {
+        self.loaded_internal_chat_id.as_ref() == Some(&chat.internal_id)
     }
 
     pub fn get_active_chats(&self) -> &StatefulOrderedList<Chat> {
-        if self.search_results.is_empty() {
-            &self.chats
-        } else {
+        if self.is_searching {
             &self.search_results
+        } else {
+            &self.visible_chats
         }
     }
 
     pub fn get_active_chats_mut(&mut self) -> &mut StatefulOrderedList<Chat> {
-        if self.search_results.is_empty() {
-            &mut self.chats
-        } else {
+        if self.is_searching {
             &mut self.search_results
+        } else {
+            &mut self.visible_chats
         }
     }
 
@@ -135,20 +507,60 @@ impl ChatManager {
         self.messages.get(&chat_id).expect("Chat messages not found")
     }
 
-    /// Search results combines both existing chats and users with which the user can start a new chat
-    /// potential new chats do not have an id, only a random internal id
-    pub fn set_search_results(&mut self, search_results: Vec<Chat>) {
-        self.clear_search_results();
+    /// Texts of the messages sent by the user without repeats, the newest first.
+    /// The messages of all chats are taken if no chat is given
+    pub fn get_sent_messages(&self, user_id: &str, chat_id: Option<ChatId>) -> Vec<String> {
+        let mut messages: Vec<&Message> = self
+            .messages
+            .iter()
+            .filter(|(id, _)| chat_id.is_none_or(|chat_id| chat_id == **id))
+            .flat_map(|(_, messages)| messages.iter())
+            .filter(|message| message.sender_id == user_id)
+            .collect();
+        messages.sort_by(|a, b| b.created_at.total_cmp(&a.created_at));
+        let mut seen = HashSet::new();
+        messages
+            .into_iter()
+            .filter(|message| seen.insert(message.text.as_str()))
+            .map(|message| message.text.clone())
+            .collect()
+    }
+
+    /// Replaces the search results with the chats whose name or members match the query.
+    /// The current user is not matched, otherwise every chat would match their name.
+    /// The selection is kept if the selected chat still matches
+    pub fn filter_chats(&mut self, query: &str, current_user_id: Option<&str>) {
+        let query = query.to_lowercase();
+        self.search_results.items = self
+            .chats
+            .items
+            .iter()
+            .filter(|chat| {
+                chat.name.to_lowercase().contains(&query) || chat.members.iter().any(|member| {
+                    Some(member.id.as_str()) != current_user_id && member.username.to_lowercase().contains(&query)
+                })
+            })
+            .cloned()
+            .collect();
+        self.search_results.update_order();
+        self.is_searching = true;
+    }
+
+    /// Search results combine both existing chats and users with which the user can start a new chat.
+    /// Potential new chats do not have an id, their internal id is derived from the user id,
+    /// so a user found again is not added twice
+    pub fn add_search_results(&mut self, search_results: Vec<Chat>) {
+        let search_results = search_results
+            .into_iter()
+            .filter(|chat| !self.search_results.contains(&chat.internal_id))
+            .collect();
         self.search_results.extend(search_results);
+        self.is_searching = true;
     }
 
     pub fn clear_search_results(&mut self) {
         self.search_results = StatefulOrderedList::default();
-    }
-
-    pub fn read_all(&mut self, chat_id: ChatId) {
-        let chat = self.chats.get_mut(&chat_id.to_string());
-        chat.number_of_unread_messages = 0;
+        self.is_searching = false;
     }
 
     pub fn select_previous_chat(&mut self) {
@@ -158,4 +570,48 @@ impl ChatManager {
     pub fn select_next_chat(&mut self) {
         self.get_active_chats_mut().next();
     }
+
+    pub fn select_first_chat(&mut self) {
+        self.get_active_chats_mut().first();
+    }
+
+    pub fn select_last_chat(&mut self) {
+        self.get_active_chats_mut().last();
+    }
+
+    /// Searches the cached message history, newest messages first
+    pub fn search_messages(&mut self, query: &str) {
+        let hits = self
+            .message_index
+            .search(query)
+            .into_iter()
+            .map(|message_ref| {
+                let message = &self.messages[&message_ref.chat_id][message_ref.index];
+                MessageSearchHit {
+                    chat_id: message_ref.chat_id,
+                    chat_name: self.get_chat(message_ref.chat_id).name.clone(),
+                    message_index: message_ref.index,
+                    sender_username: message.sender_username.clone(),
+                    text: message.text.clone(),
+                    created_at: message.created_at,
+                }
+            })
+            .collect();
+
+        self.message_search_results = StatefulOrderedList::default();
+        self.message_search_results.extend(hits);
+    }
+
+    pub fn clear_message_search_results(&mut self) {
+        self.message_search_results = StatefulOrderedList::default();
+    }
+
+    pub fn get_message_search_results_mut(&mut self) -> &mut StatefulOrderedList<MessageSearchHit> {
+        &mut self.message_search_results
+    }
+
+    pub fn get_selected_message_search_hit(&self) -> Option<MessageSearchHit> {
+        let hit_id = self.message_search_results.selected_item_id.as_ref()?;
+        Some(self.message_search_results.get(hit_id).clone())
+    }
 }
@@ -1,11 +1,102 @@
 pub mod builder;
+pub mod directory;
+pub mod index;
 pub mod manager;
 
 use std::cmp::Ordering;
+use std::time::Instant;
 use serde::{Deserialize, Serialize};
-use crate::schemas::User;
-use crate::helpers::types::ChatId;
+use crate::schemas::{Attachment, User};
+use crate::helpers::types::{ChatId, UserId};
 use crate::helpers::traits::InternalID;
+use crate::settings::Folder;
+
+const HOUR: f64 = 60.0 * 60.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MuteDuration {
+    OneHour,
+    EightHours,
+    Forever,
+}
+
+impl MuteDuration {
+    /// The next duration when cycling through the mute options, None means unmuted
+    pub fn next(duration: Option<MuteDuration>) -> Option<MuteDuration> {
+        match duration {
+            None => Some(MuteDuration::OneHour),
+            Some(MuteDuration::OneHour) => Some(MuteDuration::EightHours),
+            Some(MuteDuration::EightHours) => Some(MuteDuration::Forever),
+            Some(MuteDuration::Forever) => None,
+        }
+    }
+
+    fn seconds(&self) -> Option<f64> {
+        match self {
+            MuteDuration::OneHour => Some(HOUR),
+            MuteDuration::EightHours => Some(8.0 * HOUR),
+            MuteDuration::Forever => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Mute {
+    pub duration: MuteDuration,
+    /// Unix timestamp (in seconds) when the mute ends, None if the chat is muted forever
+    pub until: Option<f64>,
+}
+
+impl Mute {
+    pub fn new(duration: MuteDuration, now: f64) -> Self {
+        Self {
+            duration,
+            until: duration.seconds().map(|seconds| now + seconds),
+        }
+    }
+
+    pub fn is_expired(&self, now: f64) -> bool {
+        self.until.is_some_and(|until| until <= now)
+    }
+}
+
+/// Filter of the chat list, selected with the tabs above it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChatFilter {
+    #[default]
+    All,
+    Unread,
+    Direct,
+    Groups,
+    /// Index of a folder from the settings
+    Folder(usize),
+}
+
+impl ChatFilter {
+    pub const BUILT_IN: [ChatFilter; 4] = [ChatFilter::All, ChatFilter::Unread, ChatFilter::Direct, ChatFilter::Groups];
+
+    pub fn matches(&self, chat: &Chat, folders: &[Folder]) -> bool {
+        match self {
+            ChatFilter::All => true,
+            ChatFilter::Unread => chat.number_of_unread_messages > 0,
+            ChatFilter::Direct => chat.members.len() <= 2,
+            ChatFilter::Groups => chat.members.len() > 2,
+            ChatFilter::Folder(index) => folders
+                .get(*index)
+                .is_some_and(|folder| chat.id.is_some_and(|chat_id| folder.chats.contains(&chat_id))),
+        }
+    }
+}
+
+pub const MENTION_PREFIX: char = '@';
+
+/// Whether the text mentions the user as `@username`, punctuation after the mention is ignored
+pub fn mentions(text: &str, username: &str) -> bool {
+    text.split_whitespace()
+        .filter_map(|word| word.strip_prefix(MENTION_PREFIX))
+        .map(|word| word.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '_' && c != '-'))
+        .any(|mentioned| mentioned.eq_ignore_ascii_case(username))
+}
 
 // This chat represents both the ChatModel and the NewChatModel
 #[derive(Clone)]
@@ -16,6 +107,14 @@ pub struct Chat {
     pub members: Vec<User>,
     pub last_message: Option<Message>,
     pub number_of_unread_messages: u32,
+    /// Unread messages mentioning the current user
+    pub number_of_unread_mentions: u32,
+    /// Position among the pinned chats, pinned chats are always listed first
+    pub pin_position: Option<usize>,
+    /// Archived chats are hidden from the chat list and shown in the archived chats view
+    pub is_archived: bool,
+    /// Muted chats still count unread messages but don't notify about them
+    pub is_muted: bool,
 }
 
 impl InternalID for Chat {
@@ -26,12 +125,10 @@ impl InternalID for Chat {
 
 impl Eq for Chat {}
 
+// chats are equal when they have the same place in the list, so `==` agrees with `cmp`
 impl PartialEq for Chat {
     fn eq(&self, other: &Self) -> bool {
-        if self.last_message.is_none() || other.last_message.is_none() {
-            return false;
-        }
-        self.last_message.as_ref().unwrap().created_at == other.last_message.as_ref().unwrap().created_at
+        self.cmp(other) == Ordering::Equal
     }
 }
 
@@ -43,6 +140,13 @@ impl PartialOrd for Chat {
 
 impl Ord for Chat {
     fn cmp(&self, other: &Self) -> Ordering {
+        // lists are sorted in descending order, so the first pinned chat must be the greatest
+        match (self.pin_position, other.pin_position) {
+            (Some(position), Some(other_position)) => return other_position.cmp(&position),
+            (Some(_), None) => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (None, None) => {}
+        }
         if self.last_message.is_none() && other.last_message.is_none() {
             return Ordering::Equal;
         }
@@ -62,11 +166,90 @@ impl Ord for Chat {
     }
 }
 
+/// Delivery state of the messages sent by the current user
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeliveryState {
+    /// Confirmed by the server
+    #[default]
+    Sent,
+    /// Shown right away, waiting for the server to echo it back since the instant it was sent
+    Pending(Instant),
+    Failed,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Message {
     pub chat_id: u32,
+    pub sender_id: UserId,
     pub sender_username: String,
     pub text: String,
     pub created_at: f64,
     pub is_read: bool,
+    pub attachments: Vec<Attachment>,
+    pub client_message_id: Option<String>,
+    #[serde(skip)]
+    pub delivery_state: DeliveryState,
+    /// Whether the text contains an @mention of the current user
+    #[serde(skip)]
+    pub mentions_current_user: bool,
+}
+
+/// A message found by the local message search
+#[derive(Clone)]
+pub struct MessageSearchHit {
+    pub chat_id: ChatId,
+    pub chat_name: String,
+    pub message_index: usize,
+    pub sender_username: String,
+    pub text: String,
+    pub created_at: f64,
+}
+
+impl InternalID for MessageSearchHit {
+    fn internal_id(&self) -> String {
+        format!("{}:{}", self.chat_id, self.message_index)
+    }
+}
+
+impl Eq for MessageSearchHit {}
+
+impl PartialEq for MessageSearchHit {
+    fn eq(&self, other: &Self) -> bool {
+        self.created_at == other.created_at
+    }
+}
+
+impl PartialOrd for MessageSearchHit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MessageSearchHit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.created_at.total_cmp(&other.created_at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mentions() {
+        assert!(mentions("@alice hi", "alice"));
+        assert!(mentions("hi @Alice, how are you?", "alice"));
+        assert!(mentions("thanks @alice!", "alice"));
+        assert!(mentions("@bob_2.", "bob_2"));
+        assert!(mentions("ask @anna-maria", "anna-maria"));
+    }
+
+    #[test]
+    fn test_mentions_needs_the_whole_name() {
+        assert!(!mentions("alice hi", "alice"));
+        assert!(!mentions("@alicia hi", "alice"));
+        assert!(!mentions("@ali", "alice"));
+        assert!(!mentions("mail@alice", "alice"));
+        assert!(!mentions("@ alice", "alice"));
+    }
 }
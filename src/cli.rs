@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+use anyhow::{anyhow, bail, Result};
+use crate::{api, app, export, factory, storage};
+use crate::app::App;
+use crate::chat::manager::ChatManager;
+use crate::export::{ExportFormat, ExportTarget};
+
+const EXPORT_USAGE: &str = "Usage: mess-term-client export [--format md|json|html] [--chat <name> | --all] [--output <path>]";
+
+/// Runs a command line subcommand. Returns false if the arguments do not contain one
+/// and the interactive client should be started instead
+pub async fn run(args: &[String]) -> Result<bool> {
+    match args.first().map(String::as_str) {
+        Some("export") => {
+            run_export(&args[1..]).await?;
+            Ok(true)
+        }
+        Some("help") | Some("--help") | Some("-h") => {
+            println!("Usage: mess-term-client [export]\n\n{}", EXPORT_USAGE);
+            Ok(true)
+        }
+        Some(other) => bail!("Unknown command: {}\n{}", other, EXPORT_USAGE),
+        None => Ok(false),
+    }
+}
+
+async fn run_export(args: &[String]) -> Result<()> {
+    let mut format = ExportFormat::Markdown;
+    let mut chat_name = None;
+    let mut all_chats = false;
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" | "-f" => {
+                let name = args.next().ok_or_else(|| anyhow!("--format requires a value"))?;
+                format = ExportFormat::from_name(name).ok_or_else(|| anyhow!("Unknown export format: {}", name))?;
+            }
+            "--chat" | "-c" => {
+                chat_name = Some(args.next().ok_or_else(|| anyhow!("--chat requires a value"))?.clone());
+            }
+            "--all" | "-a" => all_chats = true,
+            "--output" | "-o" => {
+                output = Some(PathBuf::from(args.next().ok_or_else(|| anyhow!("--output requires a value"))?));
+            }
+            _ => bail!("Unknown argument: {}\n{}", arg, EXPORT_USAGE),
+        }
+    }
+
+    // the export needs only the chats and their messages, not the rest of the client state
+    let mut api_client = api::Client::new(storage::load_auth_tokens()).await;
+    if !api_client.is_authenticated() {
+        bail!("Not logged in, start the client and log in first");
+    }
+    let mut chat_builder = factory::get_chat_builder(vec![], app::get_current_user());
+    let mut chat_manager = ChatManager::new();
+    App::load_chats(&mut api_client, &mut chat_builder, &mut chat_manager).await;
+
+    let target = match (chat_name, all_chats) {
+        (Some(_), true) => bail!("--chat and --all can't be used together"),
+        (None, false) => bail!("Either --chat or --all is required\n{}", EXPORT_USAGE),
+        (None, true) => ExportTarget::AllChats,
+        (Some(name), false) => {
+            let chat = chat_manager
+                .get_chats()
+                .iter()
+                .find(|chat| chat.name == name)
+                .ok_or_else(|| anyhow!("Chat not found: {}", name))?;
+            ExportTarget::Chat(Box::new(chat.clone()))
+        }
+    };
+
+    let path = export::export(&chat_manager, target, format, &output.unwrap_or_else(storage::get_export_dir))?;
+    println!("Exported to {}", path.display());
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use std::path::PathBuf;
+use crate::export::ExportFormat;
+use crate::i18n::Strings;
+
+/// Commands typed into the search box, prefixed with `/`, or into the `:` command line in vim mode
+pub enum Command {
+    Quit,
+    Export {
+        format: ExportFormat,
+        all_chats: bool,
+        output: Option<PathBuf>,
+    },
+}
+
+pub const COMMAND_PREFIX: char = '/';
+
+pub fn is_command(input: &str) -> bool {
+    input.starts_with(COMMAND_PREFIX)
+}
+
+pub fn parse(input: &str, strings: &Strings) -> Result<Command, String> {
+    let mut parts = input.trim_start_matches(COMMAND_PREFIX).split_whitespace();
+    match parts.next() {
+        Some("q" | "quit") => Ok(Command::Quit),
+        Some("export") => {
+            let format_name = parts.next().unwrap_or("md");
+            let format = ExportFormat::from_name(format_name)
+                .ok_or_else(|| format!("{}: {}", strings.unknown_export_format, format_name))?;
+            let mut all_chats = false;
+            let mut output = None;
+            for part in parts {
+                if part == "all" {
+                    all_chats = true;
+                } else {
+                    output = Some(PathBuf::from(part));
+                }
+            }
+            Ok(Command::Export { format, all_chats, output })
+        }
+        Some(name) => Err(format!("{}: {}", strings.unknown_command, name)),
+        None => Err(strings.empty_command.to_string()),
+    }
+}
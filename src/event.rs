@@ -2,14 +2,20 @@ use std::time::Duration;
 
 use crossterm::event::{Event as CrosstermEvent, KeyEvent, MouseEvent};
 use futures::{FutureExt, StreamExt};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
+use crate::helpers::types::UserId;
+use crate::schemas::User;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum Event {
     Tick,
     Key(KeyEvent),
     Mouse(MouseEvent),
     Resize(u16, u16),
+    /// Text pasted into the terminal, comes as one event thanks to the bracketed paste
+    Paste(String),
+    /// Result of a user batch fetched in the background, None if the request failed
+    UsersFetched(Vec<UserId>, Option<Vec<User>>),
 }
 
 #[derive(Debug)]
@@ -17,17 +23,21 @@ pub struct EventHandler {
     sender: mpsc::UnboundedSender<Event>,
     receiver: mpsc::UnboundedReceiver<Event>,
     handler: tokio::task::JoinHandle<()>,
+    tick_rate: watch::Sender<Duration>,
 }
 
 impl EventHandler {
     pub fn new(tick_rate: u64) -> Self {
-        let tick_rate = Duration::from_millis(tick_rate);
+        let (tick_rate_sender, mut tick_rate_receiver) = watch::channel(Duration::from_millis(tick_rate));
         let (sender, receiver) = mpsc::unbounded_channel();
         let _sender = sender.clone();
         let handler = tokio::spawn(async move {
             let mut reader = crossterm::event::EventStream::new();
-            let mut tick = tokio::time::interval(tick_rate);
+            let mut tick = tokio::time::interval(*tick_rate_receiver.borrow_and_update());
             loop {
+                if tick_rate_receiver.has_changed().unwrap_or(false) {
+                    tick = tokio::time::interval(*tick_rate_receiver.borrow_and_update());
+                }
                 let tick_delay = tick.tick();
                 let crossterm_event = reader.next().fuse();
                 tokio::select! {
@@ -51,7 +61,8 @@ impl EventHandler {
                       },
                       CrosstermEvent::FocusGained => {
                       },
-                      CrosstermEvent::Paste(_) => {
+                      CrosstermEvent::Paste(text) => {
+                        _sender.send(Event::Paste(text)).unwrap();
                       },
                     }
                   }
@@ -62,9 +73,19 @@ impl EventHandler {
             sender,
             receiver,
             handler,
+            tick_rate: tick_rate_sender,
         }
     }
 
+    pub fn set_tick_rate(&self, tick_rate: u64) {
+        self.tick_rate.send_replace(Duration::from_millis(tick_rate));
+    }
+
+    /// Sender for the events produced outside of the handler, like the results of background requests
+    pub fn sender(&self) -> mpsc::UnboundedSender<Event> {
+        self.sender.clone()
+    }
+
     /// Receive the next event from the handler thread.
     ///
     /// This function will always block the current thread if
@@ -76,3 +97,9 @@ impl EventHandler {
             .expect("event handler thread has panicked")
     }
 }
+
+impl Drop for EventHandler {
+    fn drop(&mut self) {
+        self.handler.abort();
+    }
+}
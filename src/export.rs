@@ -0,0 +1,211 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use crate::chat::{Chat, Message};
+use crate::chat::manager::ChatManager;
+use crate::helpers;
+use crate::schemas::Attachment;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    JsonLines,
+    Html,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "md" | "markdown" => Some(ExportFormat::Markdown),
+            "json" | "jsonl" | "jsonlines" => Some(ExportFormat::JsonLines),
+            "html" => Some(ExportFormat::Html),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::JsonLines => "jsonl",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+/// Which chats go into the export
+pub enum ExportTarget {
+    Chat(Box<Chat>),
+    AllChats,
+}
+
+/// One line of a JSON Lines export
+#[derive(Serialize)]
+struct ExportedMessage<'a> {
+    chat_id: Option<u32>,
+    chat_name: &'a str,
+    sender: &'a str,
+    text: &'a str,
+    created_at: String,
+    attachments: &'a [Attachment],
+}
+
+/// Writes the chat history to `output`. If `output` is a directory or has no extension,
+/// it is treated as a directory and the file name is derived from the target
+pub fn export(chat_manager: &ChatManager, target: ExportTarget, format: ExportFormat, output: &Path) -> Result<PathBuf> {
+    let chats = match &target {
+        ExportTarget::Chat(chat) => vec![(**chat).clone()],
+        ExportTarget::AllChats => chat_manager.get_chats().clone(),
+    };
+    let history: Vec<(&Chat, &[Message])> = chats
+        .iter()
+        .map(|chat| {
+            let messages = match chat.id {
+                Some(chat_id) => chat_manager.get_messages(chat_id).as_slice(),
+                None => &[],
+            };
+            (chat, messages)
+        })
+        .collect();
+
+    let content = match format {
+        ExportFormat::Markdown => to_markdown(&history),
+        ExportFormat::JsonLines => to_json_lines(&history)?,
+        ExportFormat::Html => to_html(&history),
+    };
+
+    let path = if output.is_dir() || output.extension().is_none() {
+        let file_name = match &target {
+            ExportTarget::Chat(chat) => sanitize_file_name(&chat.name),
+            ExportTarget::AllChats => "mess-chats".to_string(),
+        };
+        output.join(format!("{}.{}", file_name, format.extension()))
+    } else {
+        output.to_path_buf()
+    };
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| anyhow!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::write(&path, content).map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))?;
+
+    Ok(path)
+}
+
+fn to_markdown(history: &[(&Chat, &[Message])]) -> String {
+    let mut out = String::new();
+    for (chat, messages) in history {
+        out.push_str(&format!("# {}\n\n", escape_markdown(&chat.name)));
+        for message in messages.iter() {
+            out.push_str(&format!(
+                "- **{}** ({}): {}\n",
+                escape_markdown(&message.sender_username),
+                helpers::format_iso_timestamp(message.created_at),
+                escape_markdown(&message.text).replace('\n', "\n  "),
+            ));
+            for attachment in message.attachments.iter() {
+                out.push_str(&format!(
+                    "  - 📎 [{}]({}) ({}, {} bytes)\n",
+                    escape_markdown(&attachment.name),
+                    attachment.url,
+                    attachment.mime_type,
+                    attachment.size,
+                ));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn to_json_lines(history: &[(&Chat, &[Message])]) -> Result<String> {
+    let mut out = String::new();
+    for (chat, messages) in history {
+        for message in messages.iter() {
+            let line = ExportedMessage {
+                chat_id: chat.id,
+                chat_name: &chat.name,
+                sender: &message.sender_username,
+                text: &message.text,
+                created_at: helpers::format_iso_timestamp(message.created_at),
+                attachments: &message.attachments,
+            };
+            out.push_str(&serde_json::to_string(&line)?);
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+fn to_html(history: &[(&Chat, &[Message])]) -> String {
+    let mut out = String::from(
+        "<!DOCTYPE html>\n\
+        <html>\n\
+        <head>\n\
+        <meta charset=\"utf-8\">\n\
+        <title>Mess chat history</title>\n\
+        <style>\n\
+        body { font-family: sans-serif; max-width: 60em; margin: 2em auto; color: #282a36; }\n\
+        .message { margin: 0.5em 0; }\n\
+        .sender { font-weight: bold; }\n\
+        .time { color: #8c9a9e; font-size: 0.85em; }\n\
+        .text { white-space: pre-wrap; }\n\
+        .attachment { margin-left: 1.5em; font-size: 0.9em; }\n\
+        </style>\n\
+        </head>\n\
+        <body>\n",
+    );
+    for (chat, messages) in history {
+        out.push_str(&format!("<section>\n<h1>{}</h1>\n", escape_html(&chat.name)));
+        for message in messages.iter() {
+            let created_at = helpers::format_iso_timestamp(message.created_at);
+            out.push_str(&format!(
+                "<div class=\"message\"><span class=\"sender\">{}</span> <time class=\"time\" datetime=\"{}\">{}</time><div class=\"text\">{}</div>\n",
+                escape_html(&message.sender_username),
+                created_at,
+                created_at,
+                escape_html(&message.text),
+            ));
+            for attachment in message.attachments.iter() {
+                out.push_str(&format!(
+                    "<div class=\"attachment\">📎 <a href=\"{}\">{}</a> ({}, {} bytes)</div>\n",
+                    escape_html(&attachment.url),
+                    escape_html(&attachment.name),
+                    escape_html(&attachment.mime_type),
+                    attachment.size,
+                ));
+            }
+            out.push_str("</div>\n");
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Escapes the characters that Markdown would treat as formatting
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_{}[]()#+-.!|<>~".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn sanitize_file_name(name: &str) -> String {
+    name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,415 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use unicode_segmentation::UnicodeSegmentation;
+use crate::helpers;
+use crate::helpers::types::TextInput;
+
+/// How many killed texts are kept for yanking
+const KILL_RING_SIZE: usize = 16;
+
+/// What the key did to the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    /// Not an editing key, the window handles it
+    Ignored,
+    CursorMoved,
+    TextChanged,
+}
+
+/// Cursor motions, shared by the readline keys and the vim mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Left,
+    Right,
+    LineStart,
+    LineEnd,
+    PreviousWordStart,
+    NextWordStart,
+    NextWordEnd,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum LastEdit {
+    #[default]
+    Other,
+    /// Consecutive kills are joined into one kill ring entry
+    Kill,
+    /// Alt-Y replaces the yanked text with the previous kill ring entry
+    Yank { start: usize, len: usize, index: usize },
+}
+
+/// Readline-style editing of a text input: the cursor, word motions and a kill ring.
+/// The same editor is used for all the inputs of a window, so the kill ring is shared between them
+#[derive(Debug, Clone, Default)]
+pub struct LineEditor {
+    cursor: usize,
+    kill_ring: Vec<String>,
+    last_edit: LastEdit,
+}
+
+impl LineEditor {
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn set_cursor(&mut self, input: &TextInput, cursor: usize) {
+        self.cursor = cursor.min(input.len());
+        self.last_edit = LastEdit::Other;
+    }
+
+    pub fn move_to_end(&mut self, input: &TextInput) {
+        self.set_cursor(input, input.len());
+    }
+
+    /// Applies the editing key to the input
+    pub fn process(&mut self, input: &mut TextInput, key_event: KeyEvent) -> Edit {
+        self.cursor = self.cursor.min(input.len());
+        let last_edit = std::mem::take(&mut self.last_edit);
+        let control = key_event.modifiers == KeyModifiers::CONTROL;
+        let alt = key_event.modifiers == KeyModifiers::ALT;
+        match key_event.code {
+            KeyCode::Char('a') if control => self.cursor = self.target(input, Motion::LineStart),
+            KeyCode::Char('e') if control => self.cursor = self.target(input, Motion::LineEnd),
+            KeyCode::Home if key_event.modifiers.is_empty() => self.cursor = self.target(input, Motion::LineStart),
+            KeyCode::End if key_event.modifiers.is_empty() => self.cursor = self.target(input, Motion::LineEnd),
+            KeyCode::Left if key_event.modifiers.is_empty() => self.cursor = self.target(input, Motion::Left),
+            KeyCode::Right if key_event.modifiers.is_empty() => self.cursor = self.target(input, Motion::Right),
+            KeyCode::Char('b') if alt => self.cursor = self.target(input, Motion::PreviousWordStart),
+            KeyCode::Left if control => self.cursor = self.target(input, Motion::PreviousWordStart),
+            KeyCode::Char('f') if alt => self.cursor = self.target(input, Motion::NextWordEnd),
+            KeyCode::Right if control => self.cursor = self.target(input, Motion::NextWordEnd),
+            KeyCode::Char('w') if control => {
+                let start = previous_whitespace_word_start(input, self.cursor);
+                self.kill(input, start, self.cursor, last_edit);
+                return Edit::TextChanged;
+            }
+            KeyCode::Backspace if alt => {
+                let start = previous_word_start(input, self.cursor);
+                self.kill(input, start, self.cursor, last_edit);
+                return Edit::TextChanged;
+            }
+            KeyCode::Char('d') if alt => {
+                let end = next_word_end(input, self.cursor);
+                self.kill(input, self.cursor, end, last_edit);
+                return Edit::TextChanged;
+            }
+            KeyCode::Char('u') if control => {
+                let start = line_start(input, self.cursor);
+                self.kill(input, start, self.cursor, last_edit);
+                return Edit::TextChanged;
+            }
+            KeyCode::Char('k') if control => {
+                let end = line_end(input, self.cursor);
+                // at the end of a line the line break is killed, joining the lines
+                let end = if end == self.cursor { (end + 1).min(input.len()) } else { end };
+                self.kill(input, self.cursor, end, last_edit);
+                return Edit::TextChanged;
+            }
+            KeyCode::Char('y') if control => {
+                self.paste(input);
+                return Edit::TextChanged;
+            }
+            KeyCode::Char('y') if alt => {
+                if let LastEdit::Yank { start, len, index } = last_edit {
+                    input.drain(start..start + len);
+                    self.cursor = start;
+                    self.yank(input, (index + self.kill_ring.len() - 1) % self.kill_ring.len());
+                }
+                return Edit::TextChanged;
+            }
+            KeyCode::Backspace if key_event.modifiers.is_empty() => {
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                    input.remove(self.cursor);
+                }
+                return Edit::TextChanged;
+            }
+            KeyCode::Delete if key_event.modifiers.is_empty() => {
+                if self.cursor < input.len() {
+                    input.remove(self.cursor);
+                }
+                return Edit::TextChanged;
+            }
+            KeyCode::Char(new_char) if key_event.modifiers.difference(KeyModifiers::SHIFT).is_empty() => {
+                self.insert(input, new_char);
+                return Edit::TextChanged;
+            }
+            _ => {
+                self.last_edit = last_edit;
+                return Edit::Ignored;
+            }
+        }
+        Edit::CursorMoved
+    }
+
+    /// Where the motion moves the cursor
+    pub fn target(&self, input: &TextInput, motion: Motion) -> usize {
+        let cursor = self.cursor.min(input.len());
+        match motion {
+            Motion::Left => cursor.saturating_sub(1),
+            Motion::Right => (cursor + 1).min(input.len()),
+            Motion::LineStart => line_start(input, cursor),
+            Motion::LineEnd => line_end(input, cursor),
+            Motion::PreviousWordStart => previous_word_start(input, cursor),
+            Motion::NextWordStart => next_word_start(input, cursor),
+            Motion::NextWordEnd => next_word_end(input, cursor),
+        }
+    }
+
+    pub fn move_cursor(&mut self, input: &TextInput, motion: Motion) {
+        let target = self.target(input, motion);
+        self.set_cursor(input, target);
+    }
+
+    /// Deletes the text between the positions into a new kill ring entry
+    pub fn kill_range(&mut self, input: &mut TextInput, start: usize, end: usize) {
+        self.cursor = start;
+        self.kill(input, start, end.min(input.len()), LastEdit::Other);
+    }
+
+    /// Copies the text between the positions into a new kill ring entry
+    pub fn copy_range(&mut self, input: &TextInput, start: usize, end: usize) {
+        let copied = helpers::input_range_to_string(input, start..end);
+        if copied.is_empty() {
+            return;
+        }
+        if self.kill_ring.len() == KILL_RING_SIZE {
+            self.kill_ring.remove(0);
+        }
+        self.kill_ring.push(copied);
+        self.last_edit = LastEdit::Other;
+    }
+
+    /// Inserts the last killed text at the cursor
+    pub fn paste(&mut self, input: &mut TextInput) {
+        self.cursor = self.cursor.min(input.len());
+        if let Some(index) = self.kill_ring.len().checked_sub(1) {
+            self.yank(input, index);
+        }
+    }
+
+    pub fn insert(&mut self, input: &mut TextInput, new_char: char) {
+        self.cursor = self.cursor.min(input.len());
+        self.last_edit = LastEdit::Other;
+        // a combining character like an accent or an emoji modifier joins the grapheme before the cursor
+        if let Some(previous) = self.cursor.checked_sub(1).map(|i| &mut input[i]) {
+            let mut joined = previous.clone();
+            joined.push(new_char);
+            if joined.graphemes(true).count() == 1 {
+                *previous = joined;
+                return;
+            }
+        }
+        input.insert(self.cursor, new_char.to_string());
+        self.cursor += 1;
+    }
+
+    pub fn insert_str(&mut self, input: &mut TextInput, text: &str) {
+        self.cursor = self.cursor.min(input.len());
+        self.replace_before_cursor(input, self.cursor, text);
+    }
+
+    /// Replaces the text between `start` and the cursor, used to complete the word before the cursor
+    pub fn replace_before_cursor(&mut self, input: &mut TextInput, start: usize, text: &str) {
+        self.cursor = self.cursor.min(input.len());
+        let len = text.graphemes(true).count();
+        input.splice(start..self.cursor, text.graphemes(true).map(String::from));
+        self.cursor = start + len;
+        self.last_edit = LastEdit::Other;
+    }
+
+    fn kill(&mut self, input: &mut TextInput, start: usize, end: usize, last_edit: LastEdit) {
+        if start == end {
+            self.last_edit = last_edit;
+            return;
+        }
+        let killed: String = input.drain(start..end).collect();
+        let is_backward = end == self.cursor;
+        self.cursor = start;
+        match self.kill_ring.last_mut() {
+            Some(last_kill) if last_edit == LastEdit::Kill => {
+                if is_backward {
+                    last_kill.insert_str(0, &killed);
+                } else {
+                    last_kill.push_str(&killed);
+                }
+            }
+            _ => {
+                if self.kill_ring.len() == KILL_RING_SIZE {
+                    self.kill_ring.remove(0);
+                }
+                self.kill_ring.push(killed);
+            }
+        }
+        self.last_edit = LastEdit::Kill;
+    }
+
+    fn yank(&mut self, input: &mut TextInput, index: usize) {
+        let text = &self.kill_ring[index];
+        let len = text.graphemes(true).count();
+        let start = self.cursor;
+        input.splice(start..start, text.graphemes(true).map(String::from));
+        self.cursor = start + len;
+        self.last_edit = LastEdit::Yank { start, len, index };
+    }
+}
+
+fn is_word_char(grapheme: &str) -> bool {
+    grapheme.chars().next().is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn is_whitespace(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+fn line_start(input: &TextInput, cursor: usize) -> usize {
+    (0..cursor).rev().find(|&i| input[i] == "\n").map_or(0, |i| i + 1)
+}
+
+fn line_end(input: &TextInput, cursor: usize) -> usize {
+    (cursor..input.len()).find(|&i| input[i] == "\n").unwrap_or(input.len())
+}
+
+fn previous_word_start(input: &TextInput, cursor: usize) -> usize {
+    let mut position = cursor;
+    while position > 0 && !is_word_char(&input[position - 1]) {
+        position -= 1;
+    }
+    while position > 0 && is_word_char(&input[position - 1]) {
+        position -= 1;
+    }
+    position
+}
+
+fn next_word_end(input: &TextInput, cursor: usize) -> usize {
+    let mut position = cursor;
+    while position < input.len() && !is_word_char(&input[position]) {
+        position += 1;
+    }
+    while position < input.len() && is_word_char(&input[position]) {
+        position += 1;
+    }
+    position
+}
+
+fn next_word_start(input: &TextInput, cursor: usize) -> usize {
+    let mut position = cursor;
+    if position < input.len() && is_word_char(&input[position]) {
+        while position < input.len() && is_word_char(&input[position]) {
+            position += 1;
+        }
+    } else if position < input.len() && !is_whitespace(&input[position]) {
+        position += 1;
+    }
+    while position < input.len() && is_whitespace(&input[position]) {
+        position += 1;
+    }
+    position
+}
+
+/// Ctrl-W deletes up to the previous whitespace like in a shell
+fn previous_whitespace_word_start(input: &TextInput, cursor: usize) -> usize {
+    let mut position = cursor;
+    while position > 0 && is_whitespace(&input[position - 1]) {
+        position -= 1;
+    }
+    while position > 0 && !is_whitespace(&input[position - 1]) {
+        position -= 1;
+    }
+    position
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor_with(text: &str) -> (LineEditor, TextInput) {
+        let mut editor = LineEditor::default();
+        let mut input = TextInput::new();
+        editor.insert_str(&mut input, text);
+        (editor, input)
+    }
+
+    fn press(editor: &mut LineEditor, input: &mut TextInput, code: KeyCode, modifiers: KeyModifiers) -> Edit {
+        editor.process(input, KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn test_word_motions() {
+        let (mut editor, input) = editor_with("foo  bar.baz qux");
+        editor.set_cursor(&input, 0);
+        assert_eq!(editor.target(&input, Motion::NextWordEnd), 3);
+        assert_eq!(editor.target(&input, Motion::NextWordStart), 5);
+
+        editor.set_cursor(&input, 8);
+        assert_eq!(editor.target(&input, Motion::NextWordStart), 9);
+        assert_eq!(editor.target(&input, Motion::PreviousWordStart), 5);
+
+        editor.move_to_end(&input);
+        assert_eq!(editor.target(&input, Motion::PreviousWordStart), 13);
+        assert_eq!(editor.target(&input, Motion::NextWordEnd), input.len());
+    }
+
+    #[test]
+    fn test_line_motions() {
+        let (mut editor, input) = editor_with("first\nsecond");
+        editor.set_cursor(&input, 8);
+        assert_eq!(editor.target(&input, Motion::LineStart), 6);
+        assert_eq!(editor.target(&input, Motion::LineEnd), input.len());
+
+        editor.set_cursor(&input, 2);
+        assert_eq!(editor.target(&input, Motion::LineStart), 0);
+        assert_eq!(editor.target(&input, Motion::LineEnd), 5);
+    }
+
+    #[test]
+    fn test_consecutive_kills_are_joined() {
+        let (mut editor, mut input) = editor_with("one two three");
+        assert_eq!(press(&mut editor, &mut input, KeyCode::Char('w'), KeyModifiers::CONTROL), Edit::TextChanged);
+        press(&mut editor, &mut input, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(helpers::input_to_string(&input), "one ");
+
+        press(&mut editor, &mut input, KeyCode::Char('y'), KeyModifiers::CONTROL);
+        assert_eq!(helpers::input_to_string(&input), "one two three");
+        assert_eq!(editor.cursor(), input.len());
+    }
+
+    #[test]
+    fn test_kill_to_line_end_joins_lines() {
+        let (mut editor, mut input) = editor_with("ab\ncd");
+        editor.set_cursor(&input, 1);
+        press(&mut editor, &mut input, KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert_eq!(helpers::input_to_string(&input), "a\ncd");
+        press(&mut editor, &mut input, KeyCode::Char('k'), KeyModifiers::CONTROL);
+        assert_eq!(helpers::input_to_string(&input), "acd");
+
+        editor.move_to_end(&input);
+        press(&mut editor, &mut input, KeyCode::Char('y'), KeyModifiers::CONTROL);
+        assert_eq!(helpers::input_to_string(&input), "acdb\n");
+    }
+
+    #[test]
+    fn test_yank_pop_cycles_the_kill_ring() {
+        let (mut editor, mut input) = editor_with("first second");
+        press(&mut editor, &mut input, KeyCode::Backspace, KeyModifiers::ALT);
+        // moving the cursor ends the kill, so the next kill is a separate entry
+        press(&mut editor, &mut input, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut editor, &mut input, KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(helpers::input_to_string(&input), " ");
+
+        press(&mut editor, &mut input, KeyCode::Char('y'), KeyModifiers::CONTROL);
+        assert_eq!(helpers::input_to_string(&input), "first ");
+        press(&mut editor, &mut input, KeyCode::Char('y'), KeyModifiers::ALT);
+        assert_eq!(helpers::input_to_string(&input), "second ");
+        press(&mut editor, &mut input, KeyCode::Char('y'), KeyModifiers::ALT);
+        assert_eq!(helpers::input_to_string(&input), "first ");
+        assert_eq!(editor.cursor(), 5);
+    }
+
+    #[test]
+    fn test_yank_pop_needs_a_yank() {
+        let (mut editor, mut input) = editor_with("text");
+        editor.copy_range(&input, 0, 2);
+        press(&mut editor, &mut input, KeyCode::Char('y'), KeyModifiers::ALT);
+        assert_eq!(helpers::input_to_string(&input), "text");
+    }
+}
@@ -15,15 +15,15 @@ pub struct StatefulOrderedList<T>
 impl<T> StatefulOrderedList<T>
     where T: InternalID + Ord + Clone
 {
-    pub fn get(&self, item_id: &String) -> &T {
-        &self.item_indices.get(item_id).map(|i| &self.items[*i]).expect("Item not found")
+    pub fn get(&self, item_id: &str) -> &T {
+        self.item_indices.get(item_id).map(|i| &self.items[*i]).expect("Item not found")
     }
 
-    pub fn get_mut(&mut self, item_id: &String) -> &mut T {
-        self.item_indices.get_mut(item_id).map(|i| &mut self.items[*i]).expect("Item not found")
+    pub fn get_mut(&mut self, item_id: &str) -> &mut T {
+        self.item_indices.get(item_id).map(|i| &mut self.items[*i]).expect("Item not found")
     }
 
-    pub fn contains(&self, item_id: &String) -> bool {
+    pub fn contains(&self, item_id: &str) -> bool {
         self.item_indices.contains_key(item_id)
     }
 
@@ -37,8 +37,8 @@ impl<T> StatefulOrderedList<T>
         self.update_order();
     }
 
-    pub fn select(&mut self, item_id: &String) {
-        self.selected_item_id = Some(item_id.clone());
+    pub fn select(&mut self, item_id: &str) {
+        self.selected_item_id = Some(item_id.to_string());
         self.update_state();
     }
 
@@ -83,6 +83,30 @@ impl<T> StatefulOrderedList<T>
         self.selected_item_id = Some(self.items[i].internal_id());
     }
 
+    /// Selects the item at the index, returns false if there is no such item
+    pub fn select_at(&mut self, index: usize) -> bool {
+        let Some(item) = self.items.get(index) else {
+            return false;
+        };
+        self.selected_item_id = Some(item.internal_id());
+        self.state.select(Some(index));
+        true
+    }
+
+    pub fn first(&mut self) {
+        if let Some(item) = self.items.first() {
+            self.selected_item_id = Some(item.internal_id());
+            self.state.select(Some(0));
+        }
+    }
+
+    pub fn last(&mut self) {
+        if let Some(item) = self.items.last() {
+            self.selected_item_id = Some(item.internal_id());
+            self.state.select(Some(self.items.len() - 1));
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.items.is_empty()
     }
@@ -96,17 +120,17 @@ impl<T> StatefulOrderedList<T>
         self.update_state();
     }
 
+    /// Syncs the list state with the selected item, the selection is dropped if the item is gone
     fn update_state(&mut self) {
-        if self.selected_item_id.is_none() {
-            self.state.select(None);
-            return;
-        }
-
         let selected_item_index = self
-            .item_indices
-            .get(self.selected_item_id.as_ref().expect("No item selected"))
-            .expect("Selected item not found");
-        self.state.select(Some(*selected_item_index));
+            .selected_item_id
+            .as_ref()
+            .and_then(|item_id| self.item_indices.get(item_id))
+            .copied();
+        if selected_item_index.is_none() {
+            self.selected_item_id = None;
+        }
+        self.state.select(selected_item_index);
     }
 }
 
@@ -1,18 +1,171 @@
+use std::io::{self, Write};
+use std::ops::Range;
 use base64::{engine::general_purpose, Engine as _};
+use chrono::{Local, SecondsFormat, TimeZone, Utc};
 use rand::RngCore;
 use rand::rngs::OsRng;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 use crate::helpers::types::TextInput;
 
+pub mod line_editor;
 pub mod list;
+pub mod time;
 pub mod traits;
 pub mod types;
 
 pub fn generate_sec_websocket_key() -> String {
     let mut key = [0u8; 16];
     OsRng.fill_bytes(&mut key);
-    general_purpose::STANDARD.encode(&key)
+    general_purpose::STANDARD.encode(key)
+}
+
+/// Random id attached to a sent message to match the server echo and to detect duplicates
+pub fn generate_client_message_id() -> String {
+    let mut id = [0u8; 16];
+    OsRng.fill_bytes(&mut id);
+    id.iter().map(|byte| format!("{:02x}", byte)).collect()
 }
 
 pub fn input_to_string(text_input: &TextInput) -> String {
-    text_input.iter().collect()
+    text_input.iter().map(String::as_str).collect()
+}
+
+pub fn string_to_input(text: &str) -> TextInput {
+    text.graphemes(true).map(String::from).collect()
+}
+
+/// Text of the graphemes in the range of the input
+pub fn input_range_to_string(text_input: &TextInput, range: Range<usize>) -> String {
+    text_input.iter().take(range.end).skip(range.start).map(String::as_str).collect()
+}
+
+/// Number of terminal cells the text takes, wide characters like CJK and emoji take two
+pub fn text_width(text: &str) -> usize {
+    text.width()
+}
+
+/// Number of terminal cells the graphemes in the range of the input take
+pub fn input_width(text_input: &TextInput, range: Range<usize>) -> usize {
+    text_input.iter().take(range.end).skip(range.start).map(|grapheme| grapheme.width()).sum()
+}
+
+/// First visible column of a single-line input, scrolled so the cursor column stays inside `width` cells
+pub fn horizontal_scroll(cursor_column: usize, width: usize) -> usize {
+    cursor_column.saturating_sub(width.saturating_sub(1))
+}
+
+/// Splits the input into lines at most `width` cells wide, breaking at newlines.
+/// Returns the grapheme ranges of the lines without the newline characters.
+/// A full last line is followed by an empty one so the cursor at the end has a place to go
+pub fn wrap_input(input: &TextInput, width: usize) -> Vec<Range<usize>> {
+    let width = width.max(1);
+    let mut lines = vec![];
+    let mut start = 0;
+    let mut line_width = 0;
+    for (i, grapheme) in input.iter().enumerate() {
+        if grapheme == "\n" {
+            lines.push(start..i);
+            start = i + 1;
+            line_width = 0;
+            continue;
+        }
+        let grapheme_width = grapheme.width();
+        if line_width + grapheme_width > width && i > start {
+            lines.push(start..i);
+            start = i;
+            line_width = 0;
+        }
+        line_width += grapheme_width;
+    }
+    lines.push(start..input.len());
+    if line_width >= width {
+        lines.push(input.len()..input.len());
+    }
+    lines
+}
+
+/// Row and column in cells of the cursor in the lines returned by `wrap_input`
+pub fn cursor_in_lines(input: &TextInput, lines: &[Range<usize>], cursor: usize) -> (usize, usize) {
+    let row = lines.iter().rposition(|line| line.start <= cursor).unwrap_or(0);
+    (row, input_width(input, lines[row].start..cursor))
+}
+
+/// Current unix timestamp in seconds, in the same form as message timestamps
+pub fn now_timestamp() -> f64 {
+    Utc::now().timestamp_millis() as f64 / 1000.0
+}
+
+/// Rings the terminal bell
+pub fn ring_bell() {
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(b"\x07");
+    let _ = stdout.flush();
+}
+
+/// Formats a unix timestamp (in seconds) as local date and time
+pub fn format_timestamp(timestamp: f64) -> String {
+    match Local.timestamp_millis_opt((timestamp * 1000.0) as i64) {
+        chrono::LocalResult::Single(datetime) => datetime.format("%d.%m.%Y %H:%M").to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Formats a unix timestamp (in seconds) as an ISO 8601 UTC date and time
+pub fn format_iso_timestamp(timestamp: f64) -> String {
+    match Utc.timestamp_millis_opt((timestamp * 1000.0) as i64) {
+        chrono::LocalResult::Single(datetime) => datetime.to_rfc3339_opts(SecondsFormat::Millis, true),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(text: &str) -> TextInput {
+        string_to_input(text)
+    }
+
+    #[test]
+    fn test_wrap_input() {
+        assert_eq!(wrap_input(&input("abcdefg"), 3), vec![0..3, 3..6, 6..7]);
+        assert_eq!(wrap_input(&input("ab\ncd"), 3), vec![0..2, 3..5]);
+        assert_eq!(wrap_input(&input(""), 3), vec![0..0]);
+        // a full last line is followed by an empty line for the cursor
+        assert_eq!(wrap_input(&input("abc"), 3), vec![0..3, 3..3]);
+        assert_eq!(wrap_input(&input("ab\n"), 3), vec![0..2, 3..3]);
+    }
+
+    #[test]
+    fn test_cursor_in_lines() {
+        let text = input("abcdefg\nhi");
+        let lines = wrap_input(&text, 3);
+        assert_eq!(lines, vec![0..3, 3..6, 6..7, 8..10]);
+        assert_eq!(cursor_in_lines(&text, &lines, 0), (0, 0));
+        assert_eq!(cursor_in_lines(&text, &lines, 3), (1, 0));
+        assert_eq!(cursor_in_lines(&text, &lines, 7), (2, 1));
+        assert_eq!(cursor_in_lines(&text, &lines, 10), (3, 2));
+    }
+
+    #[test]
+    fn test_wrap_input_counts_cells() {
+        // the wide characters take two cells
+        let text = input("日本語a");
+        assert_eq!(wrap_input(&text, 4), vec![0..2, 2..4]);
+        assert_eq!(wrap_input(&text, 3), vec![0..1, 1..2, 2..4, 4..4]);
+        assert_eq!(cursor_in_lines(&text, &wrap_input(&text, 4), 2), (1, 0));
+        assert_eq!(cursor_in_lines(&text, &wrap_input(&text, 4), 4), (1, 3));
+        // a combined character is one grapheme
+        assert_eq!(input("e\u{301}x").len(), 2);
+    }
+
+    #[test]
+    fn test_horizontal_scroll() {
+        assert_eq!(horizontal_scroll(0, 10), 0);
+        assert_eq!(horizontal_scroll(9, 10), 0);
+        assert_eq!(horizontal_scroll(10, 10), 1);
+        assert_eq!(horizontal_scroll(25, 10), 16);
+        assert_eq!(horizontal_scroll(5, 0), 5);
+    }
 }
@@ -0,0 +1,151 @@
+use chrono::{Datelike, Local, NaiveDateTime, TimeZone, Timelike};
+use crate::i18n::Strings;
+use crate::settings::{ClockFormat, TimeSettings, TimeZoneSetting};
+
+const MINUTE: f64 = 60.0;
+const HOUR: f64 = 60.0 * MINUTE;
+
+/// Converts a unix timestamp (in seconds) to the date and time in the configured timezone
+pub fn to_local(timestamp: f64, settings: &TimeSettings) -> Option<NaiveDateTime> {
+    let millis = (timestamp * 1000.0) as i64;
+    match settings.timezone {
+        TimeZoneSetting::Local => Local.timestamp_millis_opt(millis).single().map(|datetime| datetime.naive_local()),
+        TimeZoneSetting::Named(tz) => tz.timestamp_millis_opt(millis).single().map(|datetime| datetime.naive_local()),
+    }
+}
+
+/// Time of the day like "14:05" or "2:05 PM"
+pub fn format_time(timestamp: f64, settings: &TimeSettings) -> String {
+    let Some(datetime) = to_local(timestamp, settings) else {
+        return String::new();
+    };
+    match settings.clock {
+        ClockFormat::H24 => format!("{:02}:{:02}", datetime.hour(), datetime.minute()),
+        ClockFormat::H12 => {
+            let (is_pm, hour) = datetime.hour12();
+            format!("{}:{:02} {}", hour, datetime.minute(), if is_pm { "PM" } else { "AM" })
+        }
+    }
+}
+
+/// Time of a message, relative to now for the last hour if relative times are enabled
+pub fn format_message_time(timestamp: f64, now: f64, settings: &TimeSettings, strings: &Strings) -> String {
+    let elapsed = now - timestamp;
+    if settings.relative && (0.0..HOUR).contains(&elapsed) {
+        return if elapsed < MINUTE {
+            strings.just_now.to_string()
+        } else {
+            format!("{} {}", (elapsed / MINUTE) as u32, strings.minutes_ago)
+        };
+    }
+    format_time(timestamp, settings)
+}
+
+/// Day of a message like "Tuesday, 3 March", the year is added if it's not the current one
+pub fn format_day(timestamp: f64, now: f64, settings: &TimeSettings, strings: &Strings) -> String {
+    let (Some(datetime), Some(today)) = (to_local(timestamp, settings), to_local(now, settings)) else {
+        return String::new();
+    };
+    let day = format!(
+        "{}, {} {}",
+        strings.weekdays[datetime.weekday().num_days_from_monday() as usize],
+        datetime.day(),
+        strings.months[datetime.month0() as usize],
+    );
+    if datetime.year() == today.year() {
+        day
+    } else {
+        format!("{} {}", day, datetime.year())
+    }
+}
+
+/// Time of the last message in the chat list: the time for today, the weekday for the last week
+/// and the date for older messages
+pub fn format_chat_time(timestamp: f64, now: f64, settings: &TimeSettings, strings: &Strings) -> String {
+    let (Some(datetime), Some(today)) = (to_local(timestamp, settings), to_local(now, settings)) else {
+        return String::new();
+    };
+    let days_ago = (today.date() - datetime.date()).num_days();
+    match days_ago {
+        0 => format_time(timestamp, settings),
+        1..=6 => strings.weekdays_short[datetime.weekday().num_days_from_monday() as usize].to_string(),
+        _ => datetime.format("%d.%m.%y").to_string(),
+    }
+}
+
+pub fn is_same_day(timestamp: f64, other_timestamp: f64, settings: &TimeSettings) -> bool {
+    match (to_local(timestamp, settings), to_local(other_timestamp, settings)) {
+        (Some(datetime), Some(other_datetime)) => datetime.date() == other_datetime.date(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::Tz;
+    use crate::i18n::Language;
+    use super::*;
+
+    /// Tuesday, 5 March 2024 14:05 UTC
+    const NOW: f64 = 1709647500.0;
+    /// Tuesday, 5 March 2024 00:30 UTC
+    const EARLY_TODAY: f64 = 1709598600.0;
+    /// Sunday, 3 March 2024 09:00 UTC
+    const SUNDAY: f64 = 1709456400.0;
+    /// Tuesday, 20 February 2024 08:00 UTC
+    const LAST_MONTH: f64 = 1708416000.0;
+    /// Sunday, 31 December 2023 23:00 UTC
+    const LAST_YEAR: f64 = 1704063600.0;
+
+    fn settings(clock: ClockFormat, timezone: Tz, relative: bool) -> TimeSettings {
+        TimeSettings { clock, timezone: TimeZoneSetting::Named(timezone), relative }
+    }
+
+    fn utc() -> TimeSettings {
+        settings(ClockFormat::H24, Tz::UTC, false)
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(NOW, &utc()), "14:05");
+        assert_eq!(format_time(EARLY_TODAY, &utc()), "00:30");
+        assert_eq!(format_time(NOW, &settings(ClockFormat::H12, Tz::UTC, false)), "2:05 PM");
+        assert_eq!(format_time(EARLY_TODAY, &settings(ClockFormat::H12, Tz::UTC, false)), "12:30 AM");
+        assert_eq!(format_time(NOW, &settings(ClockFormat::H24, Tz::Europe__Kyiv, false)), "16:05");
+    }
+
+    #[test]
+    fn test_format_message_time() {
+        let strings = Language::English.strings();
+        let relative = settings(ClockFormat::H24, Tz::UTC, true);
+        assert_eq!(format_message_time(NOW, NOW + 30.0, &relative, strings), strings.just_now);
+        assert_eq!(format_message_time(NOW, NOW + 310.0, &relative, strings), format!("5 {}", strings.minutes_ago));
+        assert_eq!(format_message_time(NOW, NOW + 2.0 * HOUR, &relative, strings), "14:05");
+        // a message from the future because of a clock difference shows its time
+        assert_eq!(format_message_time(NOW, NOW - 30.0, &relative, strings), "14:05");
+        assert_eq!(format_message_time(NOW, NOW + 30.0, &utc(), strings), "14:05");
+    }
+
+    #[test]
+    fn test_format_day() {
+        let strings = Language::English.strings();
+        assert_eq!(format_day(EARLY_TODAY, NOW, &utc(), strings), "Tuesday, 5 March");
+        assert_eq!(format_day(LAST_YEAR, NOW, &utc(), strings), "Sunday, 31 December 2023");
+        assert_eq!(format_day(LAST_YEAR, NOW, &settings(ClockFormat::H24, Tz::Europe__Kyiv, false), strings), "Monday, 1 January");
+    }
+
+    #[test]
+    fn test_format_chat_time() {
+        let strings = Language::English.strings();
+        assert_eq!(format_chat_time(EARLY_TODAY, NOW, &utc(), strings), "00:30");
+        assert_eq!(format_chat_time(SUNDAY, NOW, &utc(), strings), "Sun");
+        assert_eq!(format_chat_time(LAST_MONTH, NOW, &utc(), strings), "20.02.24");
+    }
+
+    #[test]
+    fn test_is_same_day() {
+        assert!(is_same_day(EARLY_TODAY, NOW, &utc()));
+        assert!(!is_same_day(EARLY_TODAY, NOW, &settings(ClockFormat::H24, Tz::America__New_York, false)));
+        assert!(!is_same_day(SUNDAY, NOW, &utc()));
+    }
+}
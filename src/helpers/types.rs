@@ -1,5 +1,6 @@
 use gapbuf::GapBuffer;
 
 pub type ChatId = u32;
-pub type TextInput = GapBuffer<char>;
+/// Text of an input as grapheme clusters, so the cursor never splits a character made of several code points
+pub type TextInput = GapBuffer<String>;
 pub type UserId = String;
@@ -0,0 +1,223 @@
+/// Languages of the user interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    Ukrainian,
+    English,
+}
+
+impl Language {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.to_lowercase().as_str() {
+            "uk" | "ua" | "ukrainian" => Some(Language::Ukrainian),
+            "en" | "english" => Some(Language::English),
+            _ => None,
+        }
+    }
+
+    pub fn strings(&self) -> &'static Strings {
+        match self {
+            Language::Ukrainian => &UK,
+            Language::English => &EN,
+        }
+    }
+}
+
+/// All texts shown in the user interface
+pub struct Strings {
+    pub login_tab: &'static str,
+    pub register_tab: &'static str,
+    pub username: &'static str,
+    pub password: &'static str,
+    pub password_confirmation: &'static str,
+    pub username_and_password_required: &'static str,
+    pub passwords_do_not_match: &'static str,
+    pub password_too_short: &'static str,
+    pub username_too_short: &'static str,
+
+    pub search: &'static str,
+    pub search_messages: &'static str,
+    pub chats: &'static str,
+    pub archived: &'static str,
+    pub filter_all: &'static str,
+    pub filter_unread: &'static str,
+    pub filter_direct: &'static str,
+    pub filter_groups: &'static str,
+    pub draft: &'static str,
+    pub messages: &'static str,
+    pub find_in_chat: &'static str,
+    pub search_sent_messages: &'static str,
+    pub large_message: &'static str,
+    pub confirm_large_paste: &'static str,
+    pub vim_insert: &'static str,
+    pub vim_normal: &'static str,
+    pub vim_visual: &'static str,
+    pub new_messages: &'static str,
+    pub new_messages_short: &'static str,
+    pub sending: &'static str,
+    pub not_sent: &'static str,
+    pub just_now: &'static str,
+    pub minutes_ago: &'static str,
+    /// From Monday to Sunday
+    pub weekdays: [&'static str; 7],
+    pub weekdays_short: [&'static str; 7],
+    /// Month names as used after the day number
+    pub months: [&'static str; 12],
+    pub found: &'static str,
+
+    pub hint_enter_message: &'static str,
+    pub hint_search: &'static str,
+    pub hint_chat_search: &'static str,
+    pub hint_sent_message_search: &'static str,
+    pub hint_vim_normal: &'static str,
+    pub hint_vim_visual: &'static str,
+    pub hint_default: &'static str,
+    pub hint_login: &'static str,
+    pub hint_chats: &'static str,
+    /// Names of the keys that can't be rebound, in the key binding errors of the settings
+    pub text_inputs: &'static str,
+    pub vim_mode: &'static str,
+
+    pub user_search_failed: &'static str,
+    pub unknown_export_format: &'static str,
+    pub unknown_command: &'static str,
+    pub empty_command: &'static str,
+    pub select_chat_to_export: &'static str,
+    pub exported_to: &'static str,
+    pub settings_reloaded: &'static str,
+    pub save_failed: &'static str,
+    pub muted_for_hour: &'static str,
+    pub muted_for_eight_hours: &'static str,
+    pub muted_forever: &'static str,
+    pub unmuted: &'static str,
+}
+
+pub const UK: Strings = Strings {
+    login_tab: "Увійти",
+    register_tab: "Зареєструватися",
+    username: "Ім'я користувача",
+    password: "Пароль",
+    password_confirmation: "Підтвердіть пароль",
+    username_and_password_required: "Потрібно ввести ім'я користувача та пароль.",
+    passwords_do_not_match: "Паролі не збігаються.",
+    password_too_short: "Пароль має містити щонайменше 8 символів.",
+    username_too_short: "Ім'я користувача має містити щонайменше 3 символи.",
+
+    search: "Пошук",
+    search_messages: "Пошук повідомлень",
+    chats: "Чати",
+    archived: "Архів",
+    filter_all: "Всі",
+    filter_unread: "Непрочитані",
+    filter_direct: "Особисті",
+    filter_groups: "Групи",
+    draft: "чернетка",
+    messages: "Повідомлення",
+    find_in_chat: "Пошук у чаті",
+    search_sent_messages: "Пошук надісланих",
+    large_message: "Велике повідомлення",
+    confirm_large_paste: "символів, вставлено великий текст. `Enter` - відправити, `Esc` - продовжити редагування",
+    vim_insert: "-- ВСТАВКА --",
+    vim_normal: "-- НОРМАЛЬНИЙ --",
+    vim_visual: "-- ВИДІЛЕННЯ --",
+    new_messages: "Нові повідомлення",
+    new_messages_short: "нових",
+    sending: "надсилається…",
+    not_sent: "✗ не надіслано, `Ctrl-T` - надіслати ще раз",
+    just_now: "щойно",
+    minutes_ago: "хв тому",
+    weekdays: ["Понеділок", "Вівторок", "Середа", "Четвер", "П'ятниця", "Субота", "Неділя"],
+    weekdays_short: ["Пн", "Вт", "Ср", "Чт", "Пт", "Сб", "Нд"],
+    months: ["січня", "лютого", "березня", "квітня", "травня", "червня", "липня", "серпня", "вересня", "жовтня", "листопада", "грудня"],
+    found: "Знайдено",
+
+    hint_enter_message: "`Enter` - відправити повідомлення, `Alt-Enter`/`Shift-Enter` - новий рядок, `Вгору/вниз` - надіслані повідомлення, `Ctrl-R` - пошук надісланих, `Ctrl-F` - пошук у чаті, `PgUp/PgDn`, `Ctrl-вгору/вниз` - прокрутка, `Ctrl-Home` - на початок, `Ctrl-End` - до нових повідомлень, `Ctrl-T` - надіслати ще раз, `@` - згадати учасника (`Tab`/`Enter` - вибрати), 'Esc' - закрити чат, `Ctrl-C` - закрити застосунок",
+    hint_chat_search: "`Enter`/`Стрілка вгору` - попередній збіг, `Стрілка вниз` - наступний збіг, `Esc` - закрити пошук",
+    hint_sent_message_search: "`Ctrl-R` - старіший збіг, `Enter` - вставити повідомлення, `Esc` - скасувати",
+    hint_vim_normal: "`i`/`a`/`o` - вставка, `v` - виділення, `h`/`l`/`w`/`b`/`0`/`$` - рух курсору, `x`/`dd`/`dw`/`D` - видалити, `p` - вставити, `j`/`k`/`gg`/`G` - прокрутка, `/` - пошук у чаті, `:` - команда, `Enter` - відправити, `Esc` - закрити чат",
+    hint_vim_visual: "`d` - вирізати, `y` - копіювати, `c` - замінити, `Esc` - скасувати виділення",
+    hint_search: "`Tab` - шукати користувачів/повідомлення, `/export md|json|html [all]` - експорт, `Ctrl-C` - закрити застосунок",
+    hint_default: "Натисніть `Ctrl-C` щоб закрити застосунок",
+    hint_login: "`Enter` - відправити, `Tab` - наступне поле вводу, `Alt+стрілки праворуч/ліворуч` - переключитись між вкладками",
+    hint_chats: "Використовуйте стрілки вгору/вниз щоб вибрати чат. Натисніть `Enter`, щоб відкрити чат. `p` - закріпити/відкріпити чат, `Shift-вгору/вниз` - перемістити закріплений чат, `a` - архівувати/розархівувати чат, `m` - вимкнути сповіщення на 1 год/8 год/назавжди/увімкнути, `Tab/Shift-Tab` - переключити фільтр, `A` - показати архів/всі чати. Почніть вводити текст щоб знайти користувача та натисніть `Enter`. `Esc` - назад/скинути пошук",
+    text_inputs: "поля вводу",
+    vim_mode: "режим vim",
+
+    user_search_failed: "Не вдалося знайти користувачів",
+    unknown_export_format: "Невідомий формат експорту",
+    unknown_command: "Невідома команда",
+    empty_command: "Порожня команда",
+    select_chat_to_export: "Виберіть чат для експорту або використайте `all`",
+    exported_to: "Експортовано в",
+    settings_reloaded: "Налаштування оновлено",
+    save_failed: "Не вдалося зберегти",
+    muted_for_hour: "Сповіщення вимкнено на 1 годину",
+    muted_for_eight_hours: "Сповіщення вимкнено на 8 годин",
+    muted_forever: "Сповіщення вимкнено назавжди",
+    unmuted: "Сповіщення увімкнено",
+};
+
+pub const EN: Strings = Strings {
+    login_tab: "Log in",
+    register_tab: "Register",
+    username: "Username",
+    password: "Password",
+    password_confirmation: "Confirm password",
+    username_and_password_required: "Username and password are required.",
+    passwords_do_not_match: "Passwords do not match.",
+    password_too_short: "Password must be at least 8 characters long.",
+    username_too_short: "Username must be at least 3 characters long.",
+
+    search: "Search",
+    search_messages: "Search messages",
+    chats: "Chats",
+    archived: "Archived",
+    filter_all: "All",
+    filter_unread: "Unread",
+    filter_direct: "Direct",
+    filter_groups: "Groups",
+    draft: "draft",
+    messages: "Messages",
+    find_in_chat: "Find in chat",
+    search_sent_messages: "Search sent",
+    large_message: "Large message",
+    confirm_large_paste: "characters with a large paste. `Enter` - send, `Esc` - keep editing",
+    vim_insert: "-- INSERT --",
+    vim_normal: "-- NORMAL --",
+    vim_visual: "-- VISUAL --",
+    new_messages: "New messages",
+    new_messages_short: "new",
+    sending: "sending…",
+    not_sent: "✗ not sent, `Ctrl-T` - retry",
+    just_now: "just now",
+    minutes_ago: "min ago",
+    weekdays: ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"],
+    weekdays_short: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+    months: ["January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"],
+    found: "Found",
+
+    hint_enter_message: "`Enter` - send message, `Alt-Enter`/`Shift-Enter` - new line, `Up/Down` - sent messages, `Ctrl-R` - search sent messages, `Ctrl-F` - find in chat, `PgUp/PgDn`, `Ctrl-Up/Down` - scroll, `Ctrl-Home` - jump to the oldest messages, `Ctrl-End` - jump to the newest messages, `Ctrl-T` - retry failed messages, `@` - mention a member (`Tab`/`Enter` - complete), 'Esc' - close chat, `Ctrl-C` - quit",
+    hint_chat_search: "`Enter`/`Up arrow` - previous match, `Down arrow` - next match, `Esc` - close search",
+    hint_sent_message_search: "`Ctrl-R` - older match, `Enter` - put the message into the input, `Esc` - cancel",
+    hint_vim_normal: "`i`/`a`/`o` - insert, `v` - visual, `h`/`l`/`w`/`b`/`0`/`$` - move, `x`/`dd`/`dw`/`D` - delete, `p` - paste, `j`/`k`/`gg`/`G` - scroll, `/` - find in chat, `:` - command, `Enter` - send, `Esc` - close chat",
+    hint_vim_visual: "`d` - cut, `y` - copy, `c` - change, `Esc` - cancel selection",
+    hint_search: "`Tab` - search users/messages, `/export md|json|html [all]` - export, `Ctrl-C` - quit",
+    hint_default: "Press `Ctrl-C` to quit",
+    hint_login: "`Enter` - submit, `Tab` - next input, `Alt+Left/Right arrows` - switch tabs",
+    hint_chats: "Use the up/down arrows to select a chat. Press `Enter` to open it. `p` - pin/unpin the chat, `Shift-Up/Down` - move a pinned chat, `a` - archive/unarchive the chat, `m` - mute for 1h/8h/forever/unmute, `Tab/Shift-Tab` - switch the filter, `A` - show archived/all chats. Start typing to find a user and press `Enter`. `Esc` - back/reset search",
+    text_inputs: "the text inputs",
+    vim_mode: "the vim mode",
+
+    user_search_failed: "User search failed",
+    unknown_export_format: "Unknown export format",
+    unknown_command: "Unknown command",
+    empty_command: "Empty command",
+    select_chat_to_export: "Select a chat to export or use `all`",
+    exported_to: "Exported to",
+    settings_reloaded: "Settings reloaded",
+    save_failed: "Failed to save",
+    muted_for_hour: "Muted for 1 hour",
+    muted_for_eight_hours: "Muted for 8 hours",
+    muted_forever: "Muted forever",
+    unmuted: "Unmuted",
+};
@@ -0,0 +1,481 @@
+use std::collections::HashMap;
+use std::fmt;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
+
+/// What a key does, the keys of the actions are set in the `[keys]` table of the settings.
+/// Text editing keys of the inputs and the vim mode keys are not actions and can't be rebound
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, EnumString, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum Action {
+    Quit,
+    /// Closes the chat, the search or the prompt
+    Back,
+    LogIn,
+    NextInput,
+    SwitchTab,
+    /// Searches users or messages by the text of the search box
+    Search,
+    SwitchSearchMode,
+    OpenChat,
+    PreviousChat,
+    NextChat,
+    PreviousFilter,
+    NextFilter,
+    TogglePin,
+    MovePinUp,
+    MovePinDown,
+    ToggleArchive,
+    ToggleArchivedView,
+    CycleMute,
+    SendMessage,
+    NewLine,
+    RetryFailed,
+    PreviousSentMessage,
+    NextSentMessage,
+    SearchSentMessages,
+    FindInChat,
+    /// Accepts the input of the find in chat, the sent message search or the command line
+    Accept,
+    PreviousMatch,
+    NextMatch,
+    ScrollUp,
+    ScrollDown,
+    ScrollPageUp,
+    ScrollPageDown,
+    ScrollToTop,
+    ScrollToBottom,
+}
+
+/// Where the key is pressed, an action works only on its screens
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+    Login,
+    ChatSearch,
+    ChatList,
+    MessageInput,
+    /// The find in chat, the sent message search and the command line
+    Prompt,
+}
+
+const ALL_SCREENS: &[Screen] = &[Screen::Login, Screen::ChatSearch, Screen::ChatList, Screen::MessageInput, Screen::Prompt];
+const MAIN_SCREENS: &[Screen] = &[Screen::ChatSearch, Screen::ChatList, Screen::MessageInput, Screen::Prompt];
+/// Screens with a text input, typed characters and the line editor keys go to the input
+const INPUT_SCREENS: &[Screen] = &[Screen::Login, Screen::ChatSearch, Screen::MessageInput, Screen::Prompt];
+
+/// Keys of the line editor, see `LineEditor::process`
+const LINE_EDITOR_KEYS: &[&str] = &[
+    "ctrl-a", "ctrl-e", "home", "end", "left", "right", "alt-b", "ctrl-left", "alt-f", "ctrl-right",
+    "ctrl-w", "alt-backspace", "alt-d", "ctrl-u", "ctrl-k", "ctrl-y", "alt-y", "backspace", "delete",
+];
+/// Keys of the vim mode in the chat list, see `vim::process_chat_list`
+const VIM_CHAT_LIST_KEYS: &[&str] = &["j", "k", "g", "G", "l", "/", ":"];
+
+impl Action {
+    pub fn screens(&self) -> &'static [Screen] {
+        match self {
+            Action::Quit => ALL_SCREENS,
+            Action::LogIn | Action::NextInput | Action::SwitchTab => &[Screen::Login],
+            Action::Search | Action::SwitchSearchMode => &[Screen::ChatSearch],
+            Action::PreviousChat | Action::NextChat => &[Screen::ChatSearch, Screen::ChatList],
+            Action::OpenChat
+            | Action::PreviousFilter
+            | Action::NextFilter
+            | Action::TogglePin
+            | Action::MovePinUp
+            | Action::MovePinDown
+            | Action::ToggleArchive
+            | Action::ToggleArchivedView
+            | Action::CycleMute => &[Screen::ChatList],
+            Action::SendMessage
+            | Action::NewLine
+            | Action::RetryFailed
+            | Action::PreviousSentMessage
+            | Action::NextSentMessage => &[Screen::MessageInput],
+            // pressed again in the sent message search it goes to an older match
+            Action::SearchSentMessages => &[Screen::MessageInput, Screen::Prompt],
+            Action::Accept | Action::PreviousMatch | Action::NextMatch => &[Screen::Prompt],
+            Action::Back
+            | Action::FindInChat
+            | Action::ScrollUp
+            | Action::ScrollDown
+            | Action::ScrollPageUp
+            | Action::ScrollPageDown
+            | Action::ScrollToTop
+            | Action::ScrollToBottom => MAIN_SCREENS,
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["ctrl-c"],
+            Action::Back => &["esc"],
+            Action::LogIn => &["enter"],
+            Action::NextInput => &["tab"],
+            // Left and Right move the cursor of the login inputs
+            Action::SwitchTab => &["alt-left", "alt-right"],
+            Action::Search => &["enter"],
+            Action::SwitchSearchMode => &["tab"],
+            Action::OpenChat => &["enter"],
+            Action::PreviousChat => &["up"],
+            Action::NextChat => &["down"],
+            Action::PreviousFilter => &["shift-tab"],
+            Action::NextFilter => &["tab"],
+            Action::TogglePin => &["p"],
+            Action::MovePinUp => &["shift-up"],
+            Action::MovePinDown => &["shift-down"],
+            Action::ToggleArchive => &["a"],
+            Action::ToggleArchivedView => &["shift-a"],
+            Action::CycleMute => &["m"],
+            Action::SendMessage => &["enter"],
+            Action::NewLine => &["alt-enter", "shift-enter"],
+            Action::RetryFailed => &["ctrl-t"],
+            Action::PreviousSentMessage => &["up"],
+            Action::NextSentMessage => &["down"],
+            Action::SearchSentMessages => &["ctrl-r"],
+            Action::FindInChat => &["ctrl-f"],
+            Action::Accept => &["enter"],
+            Action::PreviousMatch => &["up"],
+            Action::NextMatch => &["down"],
+            Action::ScrollUp => &["ctrl-up"],
+            Action::ScrollDown => &["ctrl-down"],
+            Action::ScrollPageUp => &["pageup"],
+            Action::ScrollPageDown => &["pagedown"],
+            Action::ScrollToTop => &["ctrl-home"],
+            Action::ScrollToBottom => &["ctrl-end"],
+        }
+    }
+}
+
+/// A key with its modifiers. Shift is a part of the character for character keys, so `shift-a` is `A`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => Self {
+                code: KeyCode::Char(c.to_uppercase().next().unwrap_or(c)),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            // Caps Lock shouldn't turn Ctrl-C into another key
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => Self {
+                code: KeyCode::Char(c.to_lowercase().next().unwrap_or(c)),
+                modifiers,
+            },
+            // Shift-Tab comes as BackTab with or without the Shift modifier depending on the terminal
+            KeyCode::BackTab => Self { code, modifiers: modifiers - KeyModifiers::SHIFT },
+            _ => Self { code, modifiers },
+        }
+    }
+
+}
+
+impl From<KeyEvent> for Key {
+    fn from(key_event: KeyEvent) -> Self {
+        Self::new(key_event.code, key_event.modifiers)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (modifier, name) in [(KeyModifiers::CONTROL, "ctrl-"), (KeyModifiers::ALT, "alt-"), (KeyModifiers::SHIFT, "shift-")] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::BackTab => write!(f, "shift-tab"),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => write!(f, "{}", NAMED_KEYS.iter().find(|(_, named)| *named == code).map_or("?", |(name, _)| name)),
+        }
+    }
+}
+
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+];
+
+/// Keys of the QWERTY layout, a layout table lists the keys of another layout at the same places
+pub const QWERTY_KEYS: &str = "qwertyuiop[]asdfghjkl;'zxcvbnm,.`";
+
+/// Layout tables of the Ukrainian and Russian layouts, other layouts are added in the `[layouts]` table of the settings
+const BUILT_IN_LAYOUTS: &[&str] = &["йцукенгшщзхїфівапролджєячсмитьбюґ", "йцукенгшщзхъфывапролджэячсмитьбюё"];
+
+/// Parses a key like `ctrl-c`, `shift-up`, `alt-enter` or `A`
+pub fn parse_key(text: &str) -> Result<Key, String> {
+    let text = text.trim();
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = text;
+    // a single character is a key even if it's `-`
+    while rest.chars().count() > 1 {
+        let Some((modifier, tail)) = rest.split_once('-') else {
+            break;
+        };
+        modifiers |= match modifier.to_lowercase().as_str() {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return Err(format!("unknown modifier `{}` in `{}`, expected `ctrl`, `alt` or `shift`", modifier, text)),
+        };
+        rest = tail;
+    }
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => {
+            let name = rest.to_lowercase();
+            match name.as_str() {
+                "space" => KeyCode::Char(' '),
+                "backtab" => KeyCode::BackTab,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+                _ => match NAMED_KEYS.iter().find(|(named, _)| *named == name) {
+                    Some((_, code)) => *code,
+                    None => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                        Some(n @ 1..=12) => KeyCode::F(n),
+                        _ => return Err(format!("unknown key `{}`", text)),
+                    },
+                },
+            }
+        }
+    };
+    Ok(Key::new(code, modifiers))
+}
+
+/// What handles a key that can't be rebound
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixedKeyUser {
+    TextInputs,
+    VimMode,
+}
+
+/// Keys of the actions
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    keys: HashMap<Action, Vec<Key>>,
+    /// Keys of the other layouts to the keys at the same places of the QWERTY layout, so that the
+    /// bindings work whatever layout is active: crossterm doesn't tell the key of the base layout
+    layout_keys: HashMap<char, char>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let keys = Action::iter()
+            .map(|action| {
+                let keys = action
+                    .default_keys()
+                    .iter()
+                    .map(|key| parse_key(key).expect("Invalid default key"))
+                    .collect();
+                (action, keys)
+            })
+            .collect();
+        let mut keymap = Self { keys, layout_keys: HashMap::new() };
+        for layout in BUILT_IN_LAYOUTS {
+            keymap.add_layout(layout).expect("Invalid built-in layout");
+        }
+        keymap
+    }
+}
+
+impl Keymap {
+    /// Replaces the keys of the action, an empty list unbinds it
+    pub fn bind(&mut self, action: Action, keys: Vec<Key>) {
+        self.keys.insert(action, keys);
+    }
+
+    /// Adds a layout table, the keys of the layout at the places of `QWERTY_KEYS`
+    pub fn add_layout(&mut self, layout: &str) -> Result<(), String> {
+        let count = layout.chars().count();
+        if count != QWERTY_KEYS.chars().count() {
+            return Err(format!(
+                "the layout has {} keys, expected {}: the keys at the places of `{}`",
+                count,
+                QWERTY_KEYS.chars().count(),
+                QWERTY_KEYS,
+            ));
+        }
+        for (key, qwerty_key) in layout.chars().zip(QWERTY_KEYS.chars()) {
+            let key = key.to_lowercase().next().unwrap_or(key);
+            // the keys of the QWERTY layout itself stay as they are
+            if key != qwerty_key && !QWERTY_KEYS.contains(key) {
+                self.layout_keys.insert(key, qwerty_key);
+            }
+        }
+        Ok(())
+    }
+
+    /// The action of the pressed key on the screen. A key of another layout falls back to the same key
+    /// of the QWERTY layout, so `Ctrl-С` of the Ukrainian layout works as `Ctrl-C`
+    pub fn get_action(&self, screen: Screen, key_event: KeyEvent) -> Option<Action> {
+        let key = Key::from(key_event);
+        self.find(screen, key).or_else(|| self.in_qwerty_layout(key).and_then(|key| self.find(screen, key)))
+    }
+
+    pub fn is_bound(&self, action: Action, key_event: KeyEvent) -> bool {
+        let key = Key::from(key_event);
+        let keys = &self.keys[&action];
+        keys.contains(&key) || self.in_qwerty_layout(key).is_some_and(|key| keys.contains(&key))
+    }
+
+    /// The key at the same place of the QWERTY layout, if the key is from one of the layout tables
+    fn in_qwerty_layout(&self, key: Key) -> Option<Key> {
+        let KeyCode::Char(c) = key.code else {
+            return None;
+        };
+        let qwerty_key = *self.layout_keys.get(&c.to_lowercase().next().unwrap_or(c))?;
+        let qwerty_key = if c.is_uppercase() { qwerty_key.to_ascii_uppercase() } else { qwerty_key };
+        Some(Key { code: KeyCode::Char(qwerty_key), modifiers: key.modifiers })
+    }
+
+    /// Actions that share a key on a screen, checked when the settings are loaded
+    pub fn find_conflicts(&self) -> Vec<(Key, Action, Action)> {
+        let mut conflicts = vec![];
+        let actions: Vec<Action> = Action::iter().collect();
+        for (i, first) in actions.iter().enumerate() {
+            for second in &actions[i + 1..] {
+                if !first.screens().iter().any(|screen| second.screens().contains(screen)) {
+                    continue;
+                }
+                for key in &self.keys[first] {
+                    if self.keys[second].contains(key) {
+                        conflicts.push((*key, *first, *second));
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Actions bound to the keys the inputs and the vim mode handle themselves. An action hides its key
+    /// from the line editor, and the vim mode takes its keys before the actions
+    pub fn find_fixed_key_conflicts(&self, vim: bool) -> Vec<(Key, Action, FixedKeyUser)> {
+        let parse = |keys: &[&str]| -> Vec<Key> {
+            keys.iter().map(|key| parse_key(key).expect("Invalid fixed key")).collect()
+        };
+        let line_editor_keys = parse(LINE_EDITOR_KEYS);
+        let vim_keys = if vim { parse(VIM_CHAT_LIST_KEYS) } else { vec![] };
+        let is_typed = |key: &Key| {
+            matches!(key.code, KeyCode::Char(_)) && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        };
+        let mut conflicts = vec![];
+        for action in Action::iter() {
+            let screens = action.screens();
+            let has_input = screens.iter().any(|screen| INPUT_SCREENS.contains(screen));
+            for key in &self.keys[&action] {
+                if has_input && (is_typed(key) || line_editor_keys.contains(key)) {
+                    conflicts.push((*key, action, FixedKeyUser::TextInputs));
+                }
+                if screens.contains(&Screen::ChatList) && vim_keys.contains(key) {
+                    conflicts.push((*key, action, FixedKeyUser::VimMode));
+                }
+            }
+        }
+        conflicts
+    }
+
+    fn find(&self, screen: Screen, key: Key) -> Option<Action> {
+        Action::iter().find(|action| action.screens().contains(&screen) && self.keys[action].contains(&key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_event(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key("ctrl-c"), Ok(Key::new(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+        assert_eq!(parse_key(" Alt-Enter "), Ok(Key::new(KeyCode::Enter, KeyModifiers::ALT)));
+        assert_eq!(parse_key("shift-a"), Ok(Key::new(KeyCode::Char('A'), KeyModifiers::NONE)));
+        assert_eq!(parse_key("shift-tab"), Ok(Key::new(KeyCode::BackTab, KeyModifiers::NONE)));
+        assert_eq!(parse_key("space"), Ok(Key::new(KeyCode::Char(' '), KeyModifiers::NONE)));
+        assert_eq!(parse_key("-"), Ok(Key::new(KeyCode::Char('-'), KeyModifiers::NONE)));
+        assert_eq!(parse_key("ctrl--"), Ok(Key::new(KeyCode::Char('-'), KeyModifiers::CONTROL)));
+        assert_eq!(parse_key("f12"), Ok(Key::new(KeyCode::F(12), KeyModifiers::NONE)));
+        assert!(parse_key("f13").is_err());
+        assert!(parse_key("super-a").is_err());
+        assert!(parse_key("enterr").is_err());
+    }
+
+    #[test]
+    fn test_key_display_round_trips() {
+        for text in ["ctrl-c", "alt-enter", "A", "shift-tab", "space", "f5", "ctrl-alt-pageup"] {
+            assert_eq!(parse_key(text).unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_default_keymap_has_no_conflicts() {
+        let keymap = Keymap::default();
+        assert!(keymap.find_conflicts().is_empty());
+        assert!(keymap.find_fixed_key_conflicts(true).is_empty());
+    }
+
+    #[test]
+    fn test_find_conflicts() {
+        let mut keymap = Keymap::default();
+        keymap.bind(Action::TogglePin, vec![parse_key("ctrl-f").unwrap()]);
+        // the login screen doesn't share a screen with the chat list
+        keymap.bind(Action::NextInput, vec![parse_key("ctrl-f").unwrap()]);
+        assert_eq!(
+            keymap.find_conflicts(),
+            vec![(parse_key("ctrl-f").unwrap(), Action::TogglePin, Action::FindInChat)],
+        );
+    }
+
+    #[test]
+    fn test_find_fixed_key_conflicts() {
+        let mut keymap = Keymap::default();
+        keymap.bind(Action::SendMessage, vec![parse_key("ctrl-w").unwrap(), parse_key("x").unwrap()]);
+        keymap.bind(Action::TogglePin, vec![parse_key("j").unwrap()]);
+        let ctrl_w = parse_key("ctrl-w").unwrap();
+        let x = parse_key("x").unwrap();
+        let j = parse_key("j").unwrap();
+        assert_eq!(
+            keymap.find_fixed_key_conflicts(true),
+            vec![
+                (j, Action::TogglePin, FixedKeyUser::VimMode),
+                (ctrl_w, Action::SendMessage, FixedKeyUser::TextInputs),
+                (x, Action::SendMessage, FixedKeyUser::TextInputs),
+            ],
+        );
+        assert_eq!(keymap.find_fixed_key_conflicts(false).len(), 2);
+    }
+
+    #[test]
+    fn test_other_layouts_fall_back_to_qwerty() {
+        let mut keymap = Keymap::default();
+        assert_eq!(keymap.get_action(Screen::ChatList, key_event(KeyCode::Char('с'), KeyModifiers::CONTROL)), Some(Action::Quit));
+        assert_eq!(keymap.get_action(Screen::ChatList, key_event(KeyCode::Char('з'), KeyModifiers::NONE)), Some(Action::TogglePin));
+        assert!(keymap.is_bound(Action::ToggleArchivedView, key_event(KeyCode::Char('Ф'), KeyModifiers::SHIFT)));
+
+        assert_eq!(keymap.get_action(Screen::ChatList, key_event(KeyCode::Char('α'), KeyModifiers::NONE)), None);
+        keymap.add_layout(";ςερτυθιοπ[]ασδφγηξκλ΄'ζχψωβνμ,.`").unwrap();
+        assert_eq!(keymap.get_action(Screen::ChatList, key_event(KeyCode::Char('α'), KeyModifiers::NONE)), Some(Action::ToggleArchive));
+        assert!(keymap.add_layout("абв").is_err());
+    }
+}
@@ -1,38 +1,61 @@
 use std::num::NonZeroU32;
 use std::sync::Arc;
-use anyhow::Result;
-use governor::{Quota, RateLimiter};
+use anyhow::{anyhow, Result};
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
 use tokio::time::sleep;
 use crate::app::App;
 use crate::event::Event;
 use crate::ui::tui;
-use crate::window::process;
+use crate::window::{process, process_mouse};
 
 mod api;
 mod auth;
 mod app;
 mod chat;
-mod constants;
+mod cli;
+mod command;
 mod event;
+mod export;
 mod factory;
 mod helpers;
+mod i18n;
+mod keymap;
 mod schemas;
+mod settings;
 mod storage;
+mod theme;
 mod ui;
 mod window;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let message_rate_limiter = Arc::new(RateLimiter::direct(Quota::per_second(NonZeroU32::new(10).unwrap())));
-    let events_rate_limiter = Arc::new(RateLimiter::direct(Quota::per_second(NonZeroU32::new(60).unwrap())));
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if cli::run(&args).await? {
+        return Ok(());
+    }
+
+    let settings_path = storage::get_settings_file_path();
+    let loaded_settings = settings::load(&settings_path).map_err(|errors| {
+        let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        anyhow!("Invalid settings in {}:\n{}", settings_path.display(), errors.join("\n"))
+    })?;
+    let settings = loaded_settings.settings;
+
+    let mut message_rate_limiter = build_rate_limiter(settings.messages_per_second);
+    let mut events_rate_limiter = build_rate_limiter(settings.events_per_second);
+    let tick_rate = settings.tick_rate_ms;
 
-    let mut app = App::new(api::Client::new(storage::load_auth_tokens()).await).await;
-    let mut tui = tui::build_tui();
+    let mut app = App::new(api::Client::new(storage::load_auth_tokens()).await, settings).await;
+    if !loaded_settings.warnings.is_empty() {
+        app.set_status(format!("{}: {}", settings_path.display(), loaded_settings.warnings.join("; ")), false);
+    }
+    let mut tui = tui::build_tui(tick_rate);
 
     tui.enter()?;
 
     while !app.should_quit() {
         tui.draw(&mut app)?;
+        app.main_window.settle_message_scroll();
 
         tokio::select! {
             _ = app.receive_message(), if message_rate_limiter.check().is_ok() => {},
@@ -40,10 +63,20 @@ async fn main() -> Result<()> {
                 match event {
                     Event::Tick => {
                         app.tick();
+                        app.resolve_users(tui.events.sender());
+                        app.run_pending_search().await;
+                        if app.reload_settings() {
+                            let settings = app.settings();
+                            tui.events.set_tick_rate(settings.tick_rate_ms);
+                            message_rate_limiter = build_rate_limiter(settings.messages_per_second);
+                            events_rate_limiter = build_rate_limiter(settings.events_per_second);
+                        }
                     },
                     Event::Key(key_event) => process(&mut app, key_event).await,
-                    Event::Mouse(_) => {},
-                    Event::Resize(_, _) => {},
+                    Event::Mouse(mouse_event) => process_mouse(&mut app, mouse_event).await,
+                    Event::Resize(width, height) => tui.resize(width, height)?,
+                    Event::Paste(text) => app.paste(&text),
+                    Event::UsersFetched(user_ids, users) => app.users_fetched(user_ids, users),
                 }
             },
             else => {
@@ -55,3 +88,7 @@ async fn main() -> Result<()> {
     tui.exit()?;
     Ok(())
 }
+
+fn build_rate_limiter(per_second: u32) -> Arc<DefaultDirectRateLimiter> {
+    Arc::new(RateLimiter::direct(Quota::per_second(NonZeroU32::new(per_second).expect("Rate limit must be positive"))))
+}
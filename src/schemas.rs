@@ -32,6 +32,8 @@ pub struct NewMessage {
     pub chat_id: ChatId,
     pub sender_id: String,
     pub text: String,
+    /// Generated by the client, the server echoes it back so the message can be matched
+    pub client_message_id: String,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -41,9 +43,21 @@ pub struct MessageModel {
     pub text: String,
     pub created_at: f64,
     pub is_read: bool,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[serde(default)]
+    pub client_message_id: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
+pub struct Attachment {
+    pub name: String,
+    pub mime_type: String,
+    pub size: u64,
+    pub url: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct User {
     pub id: String,
     pub username: String,
@@ -59,6 +73,11 @@ pub struct ChatSearchResults {
     pub chats: Vec<ChatModel>,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PinnedChats {
+    pub chat_ids: Vec<ChatId>,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct GetUsersByIdsRequest {
     pub user_ids: Vec<UserId>,
@@ -0,0 +1,495 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime};
+use chrono_tz::Tz;
+use ratatui::style::Color;
+use serde::Deserialize;
+use toml::Spanned;
+use crate::theme::{ColorDepth, DEFAULT_THEME, Theme};
+use crate::i18n::Language;
+use crate::helpers::types::ChatId;
+use crate::keymap::{self, Action, FixedKeyUser, Keymap};
+
+const SETTINGS_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Directory of the user theme files, next to the settings file
+const THEMES_DIR: &str = "themes";
+
+/// User settings, loaded from `settings.toml` in the config directory
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub language: Language,
+    pub tick_rate_ms: u64,
+    pub messages_per_second: u32,
+    pub events_per_second: u32,
+    /// Width of the chat list in percent of the terminal width
+    pub chat_list_width: u16,
+    pub theme: Theme,
+    /// Ring the terminal bell on new messages in chats that aren't muted
+    pub bell: bool,
+    pub folders: Vec<Folder>,
+    pub time: TimeSettings,
+    /// Vim-like modal editing of the message input and navigation of the lists
+    pub vim: bool,
+    pub keymap: Keymap,
+}
+
+/// How message times are shown
+#[derive(Debug, Clone, Default)]
+pub struct TimeSettings {
+    pub clock: ClockFormat,
+    pub timezone: TimeZoneSetting,
+    /// Show times of the last hour as "5 min ago"
+    pub relative: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClockFormat {
+    #[default]
+    H24,
+    H12,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeZoneSetting {
+    /// The timezone of the system
+    #[default]
+    Local,
+    Named(Tz),
+}
+
+/// User-defined group of chats, shown as a tab above the chat list
+#[derive(Debug, Clone)]
+pub struct Folder {
+    pub name: String,
+    /// Ids of the chats in the folder
+    pub chats: Vec<ChatId>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            language: Language::default(),
+            tick_rate_ms: 100,
+            messages_per_second: 10,
+            events_per_second: 60,
+            chat_list_width: 25,
+            theme: DEFAULT_THEME,
+            bell: true,
+            folders: vec![],
+            time: TimeSettings::default(),
+            vim: false,
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+/// Settings that were loaded successfully, possibly with non-fatal warnings
+pub struct LoadedSettings {
+    pub settings: Settings,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SettingsError {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawSettings {
+    language: Option<Spanned<String>>,
+    tick_rate_ms: Option<Spanned<u64>>,
+    rate_limits: RawRateLimits,
+    layout: RawLayout,
+    theme: RawTheme,
+    notifications: RawNotifications,
+    folders: Vec<RawFolder>,
+    time: RawTime,
+    editor: RawEditor,
+    /// Action names to a key or a list of keys
+    keys: BTreeMap<String, Spanned<RawKeys>>,
+    /// Layout names to the keys of the layout at the places of the QWERTY keys
+    layouts: BTreeMap<String, Spanned<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawKeys {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawEditor {
+    vim: Option<bool>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawTime {
+    format: Option<Spanned<String>>,
+    timezone: Option<Spanned<String>>,
+    relative: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct RawFolder {
+    name: Spanned<String>,
+    #[serde(default)]
+    chats: Vec<ChatId>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawNotifications {
+    bell: Option<bool>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawRateLimits {
+    messages_per_second: Option<Spanned<u32>>,
+    events_per_second: Option<Spanned<u32>>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawLayout {
+    chat_list_width: Option<Spanned<u16>>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RawTheme {
+    /// A built-in theme or a theme file in the `themes` directory next to the settings file
+    name: Option<Spanned<String>>,
+    /// Colour depth of the terminal, detected from the environment by default
+    colors: Option<Spanned<String>>,
+    fg: Option<Spanned<String>>,
+    bg: Option<Spanned<String>>,
+    active: Option<Spanned<String>>,
+    inactive: Option<Spanned<String>>,
+    error: Option<Spanned<String>>,
+    highlight_fg: Option<Spanned<String>>,
+    dimmed: Option<Spanned<String>>,
+}
+
+/// Loads the settings file. A missing file means default settings
+pub fn load(path: &Path) -> Result<LoadedSettings, Vec<SettingsError>> {
+    let themes_dir = path.with_file_name(THEMES_DIR);
+    // the theme still has to be fitted to the terminal without a settings file
+    if !path.exists() {
+        return parse("", &themes_dir);
+    }
+    let text = fs::read_to_string(path).map_err(|e| vec![SettingsError {
+        line: None,
+        message: format!("Failed to read {}: {}", path.display(), e),
+    }])?;
+
+    parse(&text, &themes_dir)
+}
+
+/// Parses the settings, user themes are looked up in `themes_dir`
+pub fn parse(text: &str, themes_dir: &Path) -> Result<LoadedSettings, Vec<SettingsError>> {
+    let mut unknown_keys = vec![];
+    let deserializer = toml::Deserializer::new(text);
+    let raw: RawSettings = serde_ignored::deserialize(deserializer, |path| unknown_keys.push(path.to_string()))
+        .map_err(|e| vec![SettingsError {
+            line: e.span().map(|span| line_of(text, span.start)),
+            message: e.message().to_string(),
+        }])?;
+
+    let mut validator = Validator { text, errors: vec![] };
+    let mut settings = Settings::default();
+
+    if let Some(language) = raw.language {
+        match Language::from_code(language.get_ref()) {
+            Some(value) => settings.language = value,
+            None => validator.error(&language, format!("unknown language `{}`, expected `uk` or `en`", language.get_ref())),
+        }
+    }
+    if let Some(tick_rate_ms) = raw.tick_rate_ms {
+        settings.tick_rate_ms = validator.in_range(&tick_rate_ms, 10, 10_000, "tick_rate_ms");
+    }
+    if let Some(value) = raw.rate_limits.messages_per_second {
+        settings.messages_per_second = validator.in_range(&value, 1, 1000, "rate_limits.messages_per_second");
+    }
+    if let Some(value) = raw.rate_limits.events_per_second {
+        settings.events_per_second = validator.in_range(&value, 1, 1000, "rate_limits.events_per_second");
+    }
+    if let Some(value) = raw.layout.chat_list_width {
+        settings.chat_list_width = validator.in_range(&value, 10, 90, "layout.chat_list_width");
+    }
+    if let Some(bell) = raw.notifications.bell {
+        settings.bell = bell;
+    }
+    for folder in raw.folders {
+        let name = folder.name.get_ref().trim();
+        if name.is_empty() {
+            validator.error(&folder.name, "folder name must not be empty".to_string());
+        } else if settings.folders.iter().any(|existing| existing.name == name) {
+            validator.error(&folder.name, format!("duplicate folder `{}`", name));
+        } else {
+            settings.folders.push(Folder { name: name.to_string(), chats: folder.chats });
+        }
+    }
+    if let Some(format) = raw.time.format {
+        match format.get_ref().as_str() {
+            "24h" => settings.time.clock = ClockFormat::H24,
+            "12h" => settings.time.clock = ClockFormat::H12,
+            value => validator.error(&format, format!("unknown time format `{}`, expected `24h` or `12h`", value)),
+        }
+    }
+    if let Some(timezone) = raw.time.timezone {
+        match timezone.get_ref().as_str() {
+            "local" => settings.time.timezone = TimeZoneSetting::Local,
+            value => match Tz::from_str(value) {
+                Ok(tz) => settings.time.timezone = TimeZoneSetting::Named(tz),
+                Err(_) => validator.error(&timezone, format!("unknown timezone `{}`, expected `local` or a name like `Europe/Kyiv`", value)),
+            },
+        }
+    }
+    if let Some(relative) = raw.time.relative {
+        settings.time.relative = relative;
+    }
+    if let Some(vim) = raw.editor.vim {
+        settings.vim = vim;
+    }
+    for (name, layout) in &raw.layouts {
+        if let Err(message) = settings.keymap.add_layout(layout.get_ref()) {
+            validator.error(layout, format!("layout `{}`: {}", name, message));
+        }
+    }
+    let mut bound_actions = vec![];
+    for (name, keys) in &raw.keys {
+        let Ok(action) = Action::from_str(name) else {
+            validator.error(keys, format!("unknown action `{}` in `keys`", name));
+            continue;
+        };
+        let names = match keys.get_ref() {
+            RawKeys::One(key) => vec![key.clone()],
+            RawKeys::Many(keys) => keys.clone(),
+        };
+        let mut parsed = vec![];
+        for key in names {
+            match keymap::parse_key(&key) {
+                Ok(key) => parsed.push(key),
+                Err(message) => validator.error(keys, message),
+            }
+        }
+        settings.keymap.bind(action, parsed);
+        bound_actions.push((action, keys));
+    }
+    for (key, first, second) in settings.keymap.find_conflicts() {
+        // the conflict comes from one of the changed actions, the defaults don't conflict
+        if let Some((_, keys)) = bound_actions.iter().rev().find(|(action, _)| *action == first || *action == second) {
+            validator.error(*keys, format!("`{}` is bound to both `{}` and `{}`", key, first, second));
+        }
+    }
+    let strings = settings.language.strings();
+    for (key, action, used_by) in settings.keymap.find_fixed_key_conflicts(settings.vim) {
+        if let Some((_, keys)) = bound_actions.iter().rev().find(|(bound, _)| *bound == action) {
+            let used_by = match used_by {
+                FixedKeyUser::TextInputs => strings.text_inputs,
+                FixedKeyUser::VimMode => strings.vim_mode,
+            };
+            validator.error(*keys, format!("`{}` of `{}` is already used by {}", key, action, used_by));
+        }
+    }
+    let theme = raw.theme;
+    if let Some(name) = theme.name {
+        match Theme::built_in(name.get_ref()) {
+            Some(built_in) => settings.theme = built_in,
+            None => {
+                let path = themes_dir.join(format!("{}.toml", name.get_ref()));
+                if path.exists() {
+                    match Theme::load(&path) {
+                        Ok(loaded) => settings.theme = loaded,
+                        Err(message) => validator.error(&name, message),
+                    }
+                } else {
+                    validator.error(&name, format!(
+                        "unknown theme `{}`, expected one of {} or a theme file {}",
+                        name.get_ref(),
+                        Theme::built_in_names().iter().map(|name| format!("`{}`", name)).collect::<Vec<_>>().join(", "),
+                        path.display(),
+                    ));
+                }
+            }
+        }
+    }
+    for (value, color) in [
+        (theme.fg, &mut settings.theme.fg),
+        (theme.bg, &mut settings.theme.bg),
+        (theme.active, &mut settings.theme.active),
+        (theme.inactive, &mut settings.theme.inactive),
+        (theme.error, &mut settings.theme.error),
+        (theme.highlight_fg, &mut settings.theme.highlight_fg),
+        (theme.dimmed, &mut settings.theme.dimmed),
+    ] {
+        if let Some(value) = value {
+            *color = validator.color(&value).unwrap_or(*color);
+        }
+    }
+    // an explicit colour depth wins over `NO_COLOR` like the other user settings
+    let color_depth = match theme.colors {
+        Some(colors) if colors.get_ref() != "auto" => ColorDepth::from_name(colors.get_ref()).unwrap_or_else(|| {
+            validator.error(&colors, format!("unknown colors `{}`, expected `auto`, `truecolor`, `256`, `16` or `none`", colors.get_ref()));
+            ColorDepth::TrueColor
+        }),
+        _ => ColorDepth::detect(),
+    };
+    settings.theme = settings.theme.for_color_depth(color_depth);
+
+    if !validator.errors.is_empty() {
+        return Err(validator.errors);
+    }
+
+    let warnings = unknown_keys
+        .into_iter()
+        .map(|key| format!("unknown key `{}` is ignored", key))
+        .collect();
+    Ok(LoadedSettings { settings, warnings })
+}
+
+struct Validator<'a> {
+    text: &'a str,
+    errors: Vec<SettingsError>,
+}
+
+impl Validator<'_> {
+    fn error<T>(&mut self, value: &Spanned<T>, message: String) {
+        self.errors.push(SettingsError {
+            line: Some(line_of(self.text, value.span().start)),
+            message,
+        });
+    }
+
+    fn in_range<T>(&mut self, value: &Spanned<T>, min: T, max: T, name: &str) -> T
+        where T: PartialOrd + Copy + fmt::Display
+    {
+        let v = *value.get_ref();
+        if v < min || v > max {
+            self.error(value, format!("`{}` must be between {} and {}, got {}", name, min, max, v));
+        }
+        v
+    }
+
+    fn color(&mut self, value: &Spanned<String>) -> Option<Color> {
+        match Color::from_str(value.get_ref()) {
+            Ok(color) => Some(color),
+            Err(_) => {
+                self.error(value, format!("invalid color `{}`, expected a name or `#rrggbb`", value.get_ref()));
+                None
+            }
+        }
+    }
+}
+
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+/// Reloads the settings when the settings file or a theme file in the themes directory changes
+pub struct SettingsWatcher {
+    path: PathBuf,
+    modified: Vec<(PathBuf, Option<SystemTime>)>,
+    checked_at: Instant,
+}
+
+impl SettingsWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let modified = get_modified(&path);
+        Self {
+            path,
+            modified,
+            checked_at: Instant::now(),
+        }
+    }
+
+    pub fn poll(&mut self) -> Option<Result<LoadedSettings, Vec<SettingsError>>> {
+        if self.checked_at.elapsed() < SETTINGS_CHECK_INTERVAL {
+            return None;
+        }
+        self.checked_at = Instant::now();
+
+        let modified = get_modified(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        Some(load(&self.path))
+    }
+}
+
+/// Modification times of the settings file and the theme files
+fn get_modified(path: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    let mut theme_paths: Vec<PathBuf> = fs::read_dir(path.with_file_name(THEMES_DIR))
+        .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    theme_paths.retain(|theme_path| theme_path.extension().is_some_and(|extension| extension == "toml"));
+    theme_paths.sort();
+    std::iter::once(path.to_path_buf())
+        .chain(theme_paths)
+        .map(|path| {
+            let modified = modified(&path);
+            (path, modified)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_text(text: &str) -> Result<LoadedSettings, Vec<SettingsError>> {
+        parse(text, Path::new("themes"))
+    }
+
+    fn error_lines(text: &str) -> Vec<Option<usize>> {
+        match parse_text(text) {
+            Ok(_) => vec![],
+            Err(errors) => errors.iter().map(|error| error.line).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_values() {
+        let loaded = parse_text("tick_rate_ms = 100\n\n[rate_limits]\nmessages_per_second = 5\n").unwrap();
+        assert_eq!(loaded.settings.tick_rate_ms, 100);
+        assert_eq!(loaded.settings.messages_per_second, 5);
+        assert!(loaded.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_errors_have_line_numbers() {
+        assert_eq!(error_lines("tick_rate_ms = 100\ntick_rate_ms = 5\n"), vec![Some(2)]);
+        assert_eq!(error_lines("tick_rate_ms = 5\n[layout]\nchat_list_width = 95\n"), vec![Some(1), Some(3)]);
+        assert_eq!(error_lines("\n\ntick_rate_ms = \"fast\"\n"), vec![Some(3)]);
+    }
+
+    #[test]
+    fn test_unknown_keys_are_warnings() {
+        let loaded = parse_text("tick_rate = 100\n[layout]\nwidth = 30\n").unwrap();
+        assert_eq!(loaded.warnings, vec![
+            "unknown key `tick_rate` is ignored".to_string(),
+            "unknown key `layout.width` is ignored".to_string(),
+        ]);
+    }
+}
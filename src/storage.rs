@@ -1,6 +1,11 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use crate::auth::AuthTokens;
+use crate::chat::Mute;
+use crate::helpers::types::ChatId;
 use crate::schemas::User;
 
 pub fn load_auth_tokens() -> Option<AuthTokens> {
@@ -127,6 +132,92 @@ fn get_refresh_token_file_path() -> PathBuf {
     get_credentials_dir().join("mess_refresh_token.txt")
 }
 
+pub fn load_pinned_chats() -> Vec<ChatId> {
+    load_state_file(&get_pinned_chats_file_path()).unwrap_or_default()
+}
+
+pub fn store_pinned_chats(chat_ids: &[ChatId]) -> Result<(), String> {
+    store_state_file(&get_pinned_chats_file_path(), chat_ids)
+}
+
+pub fn load_archived_chats() -> Vec<ChatId> {
+    load_state_file(&get_archived_chats_file_path()).unwrap_or_default()
+}
+
+pub fn store_archived_chats(chat_ids: &[ChatId]) -> Result<(), String> {
+    store_state_file(&get_archived_chats_file_path(), chat_ids)
+}
+
+pub fn load_muted_chats() -> HashMap<ChatId, Mute> {
+    load_state_file(&get_muted_chats_file_path()).unwrap_or_default()
+}
+
+pub fn store_muted_chats(mutes: &HashMap<ChatId, Mute>) -> Result<(), String> {
+    store_state_file(&get_muted_chats_file_path(), mutes)
+}
+
+pub fn load_drafts() -> HashMap<String, String> {
+    load_state_file(&get_drafts_file_path()).unwrap_or_default()
+}
+
+pub fn store_drafts(drafts: &HashMap<String, String>) -> Result<(), String> {
+    store_state_file(&get_drafts_file_path(), drafts)
+}
+
+/// Reads a state file, a missing file gives the default state. The callers fall back to the default
+/// state on errors too, a broken state file shouldn't keep the client from starting
+fn load_state_file<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    if !path.exists() {
+        return Ok(T::default());
+    }
+
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn store_state_file<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    let data_dir = get_data_dir();
+    fs::create_dir_all(&data_dir).map_err(|e| format!("{}: {}", data_dir.display(), e))?;
+
+    let content = serde_json::to_string(value).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+pub fn get_settings_file_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| dirs::home_dir().expect("Home directory not found").join(".config"))
+        .join("mess-term-client")
+        .join("settings.toml")
+}
+
+pub fn get_export_dir() -> PathBuf {
+    dirs::download_dir()
+        .unwrap_or_else(|| dirs::home_dir().expect("Home directory not found"))
+        .join("mess-exports")
+}
+
+fn get_data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| dirs::home_dir().expect("Home directory not found").join(".local").join("share"))
+        .join("mess-term-client")
+}
+
+fn get_pinned_chats_file_path() -> PathBuf {
+    get_data_dir().join("pinned_chats.json")
+}
+
+fn get_archived_chats_file_path() -> PathBuf {
+    get_data_dir().join("archived_chats.json")
+}
+
+fn get_muted_chats_file_path() -> PathBuf {
+    get_data_dir().join("muted_chats.json")
+}
+
+fn get_drafts_file_path() -> PathBuf {
+    get_data_dir().join("drafts.json")
+}
+
 fn get_credentials_dir() -> PathBuf {
     dirs::home_dir().expect("Home directory not found").join(".credentials")
 }
@@ -0,0 +1,294 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+/// Colours of the interface, every widget takes its styles from the active theme
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub fg: Color,
+    pub bg: Color,
+    pub active: Color,
+    pub inactive: Color,
+    pub error: Color,
+    /// Text of the selected list item or tab, drawn on the highlight colour
+    pub highlight_fg: Color,
+    /// Lists of the panel without the focus
+    pub dimmed: Color,
+}
+
+pub const DARK_THEME: Theme = Theme {
+    fg: Color::Rgb(147, 183, 190),
+    bg: Color::Rgb(40, 42, 54),
+    active: Color::Rgb(212, 245, 245),
+    inactive: Color::Rgb(140, 154, 158),
+    error: Color::Rgb(165, 117, 72),
+    highlight_fg: Color::Black,
+    dimmed: Color::DarkGray,
+};
+
+pub const LIGHT_THEME: Theme = Theme {
+    fg: Color::Rgb(56, 58, 66),
+    bg: Color::Rgb(250, 250, 250),
+    active: Color::Rgb(1, 132, 188),
+    inactive: Color::Rgb(120, 122, 130),
+    error: Color::Rgb(202, 18, 67),
+    highlight_fg: Color::Rgb(250, 250, 250),
+    dimmed: Color::Rgb(170, 172, 178),
+};
+
+pub const HIGH_CONTRAST_THEME: Theme = Theme {
+    fg: Color::White,
+    bg: Color::Black,
+    active: Color::Yellow,
+    inactive: Color::Gray,
+    error: Color::LightRed,
+    highlight_fg: Color::Black,
+    dimmed: Color::Gray,
+};
+
+pub const SOLARIZED_THEME: Theme = Theme {
+    fg: Color::Rgb(131, 148, 150),
+    bg: Color::Rgb(0, 43, 54),
+    active: Color::Rgb(42, 161, 152),
+    inactive: Color::Rgb(101, 123, 131),
+    error: Color::Rgb(220, 50, 47),
+    highlight_fg: Color::Rgb(0, 43, 54),
+    dimmed: Color::Rgb(88, 110, 117),
+};
+
+pub const DEFAULT_THEME: Theme = DARK_THEME;
+
+const BUILT_IN_THEMES: &[(&str, Theme)] = &[
+    ("dark", DARK_THEME),
+    ("light", LIGHT_THEME),
+    ("high-contrast", HIGH_CONTRAST_THEME),
+    ("solarized", SOLARIZED_THEME),
+];
+
+/// How many colours the terminal shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    /// `NO_COLOR` is set, only the default colours of the terminal and text modifiers are used
+    NoColor,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+impl ColorDepth {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(ColorDepth::NoColor),
+            "16" => Some(ColorDepth::Ansi16),
+            "256" => Some(ColorDepth::Ansi256),
+            "truecolor" => Some(ColorDepth::TrueColor),
+            _ => None,
+        }
+    }
+
+    /// Guesses the colour depth from the environment, see https://no-color.org for `NO_COLOR`
+    pub fn detect() -> Self {
+        let is_set = |name: &str| std::env::var(name).is_ok_and(|value| !value.is_empty());
+        if is_set("NO_COLOR") {
+            return ColorDepth::NoColor;
+        }
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorDepth::TrueColor;
+        }
+        if std::env::var("TERM").is_ok_and(|term| term.contains("256color")) {
+            return ColorDepth::Ansi256;
+        }
+        ColorDepth::Ansi16
+    }
+}
+
+/// Colours of a theme file, the missing ones are taken from the dark theme
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFile {
+    fg: Option<String>,
+    bg: Option<String>,
+    active: Option<String>,
+    inactive: Option<String>,
+    error: Option<String>,
+    highlight_fg: Option<String>,
+    dimmed: Option<String>,
+}
+
+impl Theme {
+    pub fn built_in(name: &str) -> Option<Self> {
+        BUILT_IN_THEMES.iter().find(|(theme_name, _)| *theme_name == name).map(|(_, theme)| *theme)
+    }
+
+    pub fn built_in_names() -> Vec<&'static str> {
+        BUILT_IN_THEMES.iter().map(|(name, _)| *name).collect()
+    }
+
+    /// Loads a user theme, a TOML file with the colours by name like `fg = "#d8dee9"`
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let file: ThemeFile = toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e.message()))?;
+        let mut theme = DEFAULT_THEME;
+        for (name, value, color) in [
+            ("fg", file.fg, &mut theme.fg),
+            ("bg", file.bg, &mut theme.bg),
+            ("active", file.active, &mut theme.active),
+            ("inactive", file.inactive, &mut theme.inactive),
+            ("error", file.error, &mut theme.error),
+            ("highlight_fg", file.highlight_fg, &mut theme.highlight_fg),
+            ("dimmed", file.dimmed, &mut theme.dimmed),
+        ] {
+            if let Some(value) = value {
+                *color = Color::from_str(&value)
+                    .map_err(|_| format!("{}: invalid color `{}` for `{}`, expected a name or `#rrggbb`", path.display(), value, name))?;
+            }
+        }
+        Ok(theme)
+    }
+
+    /// The theme with the colours the terminal can show
+    pub fn for_color_depth(&self, depth: ColorDepth) -> Self {
+        let convert = |color: Color| match depth {
+            ColorDepth::NoColor => Color::Reset,
+            ColorDepth::Ansi16 => to_ansi16(color),
+            ColorDepth::Ansi256 => to_ansi256(color),
+            ColorDepth::TrueColor => color,
+        };
+        Self {
+            fg: convert(self.fg),
+            bg: convert(self.bg),
+            active: convert(self.active),
+            inactive: convert(self.inactive),
+            error: convert(self.error),
+            highlight_fg: convert(self.highlight_fg),
+            dimmed: convert(self.dimmed),
+        }
+    }
+
+    /// Style of the selected list item or tab on the given colour. Without colours the selection is reversed
+    pub fn highlight(&self, color: Color) -> Style {
+        let style = Style::default().fg(self.highlight_fg).bg(color).add_modifier(Modifier::BOLD);
+        if color == Color::Reset {
+            style.add_modifier(Modifier::REVERSED)
+        } else {
+            style
+        }
+    }
+}
+
+/// Levels of the xterm 6x6x6 colour cube
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// The closest colour of the xterm 256-colour palette
+fn to_ansi256(color: Color) -> Color {
+    let Color::Rgb(r, g, b) = color else {
+        return color;
+    };
+    let nearest_level = |value: u8| {
+        (0..CUBE_LEVELS.len()).min_by_key(|&i| CUBE_LEVELS[i].abs_diff(value)).unwrap_or(0)
+    };
+    let (ri, gi, bi) = (nearest_level(r), nearest_level(g), nearest_level(b));
+    let cube = (16 + 36 * ri + 6 * gi + bi) as u8;
+    let cube_rgb = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+    // the grayscale ramp from 8 to 238 in steps of 10
+    let gray_step = ((r as usize + g as usize + b as usize) / 3).saturating_sub(3) / 10;
+    let gray_step = gray_step.min(23);
+    let gray_value = (8 + 10 * gray_step) as u8;
+    let gray = (232 + gray_step) as u8;
+    if distance((r, g, b), (gray_value, gray_value, gray_value)) < distance((r, g, b), cube_rgb) {
+        Color::Indexed(gray)
+    } else {
+        Color::Indexed(cube)
+    }
+}
+
+/// The closest of the 16 basic colours, by their usual xterm values
+fn to_ansi16(color: Color) -> Color {
+    let rgb = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        Color::Indexed(index) if index >= 16 => indexed_to_rgb(index),
+        Color::Indexed(index) => return ANSI16[index as usize].0,
+        _ => return color,
+    };
+    // a noticeably coloured colour like the error colour shouldn't turn gray
+    let (max, min) = (rgb.0.max(rgb.1).max(rgb.2), rgb.0.min(rgb.1).min(rgb.2));
+    let is_chromatic = |ansi: &(u8, u8, u8)| ansi.0 != ansi.1 || ansi.1 != ansi.2;
+    ANSI16
+        .iter()
+        .filter(|(_, ansi)| max - min < CHROMA_THRESHOLD || is_chromatic(ansi))
+        .min_by_key(|(_, ansi)| distance(rgb, *ansi))
+        .map_or(color, |(ansi_color, _)| *ansi_color)
+}
+
+/// Colours with a bigger difference between the channels map to the coloured basic colours only
+const CHROMA_THRESHOLD: u8 = 40;
+
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+fn indexed_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI16[index as usize].1,
+        16..=231 => {
+            let i = (index - 16) as usize;
+            (CUBE_LEVELS[i / 36], CUBE_LEVELS[i / 6 % 6], CUBE_LEVELS[i % 6])
+        }
+        _ => {
+            let value = 8 + 10 * (index - 232);
+            (value, value, value)
+        }
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_ansi256() {
+        assert_eq!(to_ansi256(Color::Rgb(255, 0, 0)), Color::Indexed(196));
+        assert_eq!(to_ansi256(Color::Rgb(0, 0, 0)), Color::Indexed(16));
+        assert_eq!(to_ansi256(Color::Rgb(128, 128, 128)), Color::Indexed(244));
+        assert_eq!(to_ansi256(Color::Rgb(255, 255, 255)), Color::Indexed(231));
+        assert_eq!(to_ansi256(Color::Red), Color::Red);
+    }
+
+    #[test]
+    fn test_to_ansi16() {
+        assert_eq!(to_ansi16(Color::Rgb(250, 10, 10)), Color::LightRed);
+        assert_eq!(to_ansi16(Color::Rgb(128, 128, 128)), Color::DarkGray);
+        assert_eq!(to_ansi16(Color::Indexed(3)), Color::Yellow);
+        assert_eq!(to_ansi16(Color::Indexed(196)), Color::LightRed);
+        assert_eq!(to_ansi16(Color::Reset), Color::Reset);
+    }
+
+    #[test]
+    fn test_to_ansi16_keeps_dull_colours_coloured() {
+        // the closest colour is dark gray, but a red colour should stay red
+        assert_eq!(to_ansi16(Color::Rgb(120, 60, 60)), Color::Red);
+    }
+}
@@ -4,15 +4,16 @@ use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Tabs};
 use strum::IntoEnumIterator;
 use crate::app::App;
-use crate::constants::THEME;
 use crate::helpers;
 use crate::window::login::{LoginActiveInput, LoginTabs};
 
 
 pub fn render_login_register(app: &mut App, f: &mut Frame) {
-    let titles = LoginTabs::iter().map(|t| t.to_string());
+    let strings = app.strings();
+    let theme = app.settings().theme;
+    let titles = LoginTabs::iter().map(|t| t.title(strings));
     let tabs = Tabs::new(titles)
-        .highlight_style(Style::default().bold().black().bg(THEME.active))
+        .highlight_style(theme.highlight(theme.active))
         .select(app.login_window.selected_tab as usize)
         ;
 
@@ -27,6 +28,8 @@ pub fn render_login_register(app: &mut App, f: &mut Frame) {
 }
 
 fn render_login(app: &mut App, tabs: Tabs, f: &mut Frame) {
+    let strings = app.strings();
+    let theme = app.settings().theme;
     // todo make separate error field for all fields, including register
     let login_area = create_login_area(f.size());
     let input_area = Layout::default()
@@ -46,34 +49,36 @@ fn render_login(app: &mut App, tabs: Tabs, f: &mut Frame) {
 
     let login_block = Block::default()
         .title_alignment(Alignment::Center)
-        .style(Style::default().fg(THEME.fg).bg(THEME.bg));
+        .style(Style::default().fg(theme.fg).bg(theme.bg));
 
     let username_value = helpers::input_to_string(&app.login_window.username_input);
     let username_input = Paragraph::new(username_value.as_str())
+        .scroll((0, get_input_scroll(app, matches!(app.login_window.active_input_field, LoginActiveInput::Username), input_area[3])))
         .style(match app.login_window.active_input_field {
-            LoginActiveInput::Username => Style::default().fg(THEME.active),
-            LoginActiveInput::Password => Style::default().fg(THEME.inactive),
+            LoginActiveInput::Username => Style::default().fg(theme.active),
+            LoginActiveInput::Password => Style::default().fg(theme.inactive),
             _ => unreachable!(),
         })
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Ім'я користувача")
+                .title(strings.username)
         );
     let password_value = helpers::input_to_string(&app.login_window.password_input);
     let password_input = Paragraph::new(password_value.as_str())
+        .scroll((0, get_input_scroll(app, matches!(app.login_window.active_input_field, LoginActiveInput::Password), input_area[4])))
         .style(match app.login_window.active_input_field {
-            LoginActiveInput::Username => Style::default().fg(THEME.inactive),
-            LoginActiveInput::Password => Style::default().fg(THEME.active),
+            LoginActiveInput::Username => Style::default().fg(theme.inactive),
+            LoginActiveInput::Password => Style::default().fg(theme.active),
             _ => unreachable!(),
         })
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Пароль")
+                .title(strings.password)
         );
     let error_message = Paragraph::new(app.login_window.login_error_message.as_str())
-        .style(Style::default().fg(THEME.error))
+        .style(Style::default().fg(theme.error))
         .block(
             Block::default()
         )
@@ -92,13 +97,15 @@ fn render_login(app: &mut App, tabs: Tabs, f: &mut Frame) {
         _ => unreachable!(),
     };
     f.set_cursor(
-        active_input_area.x + app.login_window.get_cursor_position() as u16 + 1,
+        active_input_area.x + app.login_window.get_cursor_column() as u16 - get_input_scroll(app, true, active_input_area) + 1,
         // Move one line down, from the border to the input line
         active_input_area.y + 1,
     );
 }
 
 fn render_register(app: &mut App, tabs: Tabs, f: &mut Frame) {
+    let strings = app.strings();
+    let theme = app.settings().theme;
     let register_area = create_register_area(f.size());
     let input_area = Layout::default()
         .direction(Direction::Vertical)
@@ -120,43 +127,46 @@ fn render_register(app: &mut App, tabs: Tabs, f: &mut Frame) {
 
     let block = Block::default()
         .title_alignment(Alignment::Center)
-        .style(Style::default().fg(THEME.fg).bg(THEME.bg));
+        .style(Style::default().fg(theme.fg).bg(theme.bg));
 
     let username_input_value = helpers::input_to_string(&app.login_window.register_username_input);
     let username_input = Paragraph::new(username_input_value.as_str())
+        .scroll((0, get_input_scroll(app, matches!(app.login_window.active_input_field, LoginActiveInput::RegisterUsername), input_area[3])))
         .style(match app.login_window.active_input_field {
-            LoginActiveInput::RegisterUsername => Style::default().fg(THEME.active),
-            _ => Style::default().fg(THEME.inactive),
+            LoginActiveInput::RegisterUsername => Style::default().fg(theme.active),
+            _ => Style::default().fg(theme.inactive),
         })
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Ім'я користувача")
+                .title(strings.username)
         );
     let password_input_value = helpers::input_to_string(&app.login_window.register_password_input);
     let password_input = Paragraph::new(password_input_value.as_str())
+        .scroll((0, get_input_scroll(app, matches!(app.login_window.active_input_field, LoginActiveInput::RegisterPassword), input_area[5])))
         .style(match app.login_window.active_input_field {
-            LoginActiveInput::RegisterPassword => Style::default().fg(THEME.active),
-            _ => Style::default().fg(THEME.inactive),
+            LoginActiveInput::RegisterPassword => Style::default().fg(theme.active),
+            _ => Style::default().fg(theme.inactive),
         })
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Пароль")
+                .title(strings.password)
         );
     let password_confirmation_input_value = helpers::input_to_string(&app.login_window.register_password_confirmation_input);
     let password_confirmation_input = Paragraph::new(password_confirmation_input_value.as_str())
+        .scroll((0, get_input_scroll(app, matches!(app.login_window.active_input_field, LoginActiveInput::RegisterPasswordConfirmation), input_area[6])))
         .style(match app.login_window.active_input_field {
-            LoginActiveInput::RegisterPasswordConfirmation => Style::default().fg(THEME.active),
-            _ => Style::default().fg(THEME.inactive),
+            LoginActiveInput::RegisterPasswordConfirmation => Style::default().fg(theme.active),
+            _ => Style::default().fg(theme.inactive),
         })
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title("Підтвердіть пароль")
+                .title(strings.password_confirmation)
         );
     let error_message = Paragraph::new(app.login_window.register_error_message.as_str())
-        .style(Style::default().fg(THEME.error))
+        .style(Style::default().fg(theme.error))
         .block(
             Block::default()
         )
@@ -177,12 +187,21 @@ fn render_register(app: &mut App, tabs: Tabs, f: &mut Frame) {
         _ => unreachable!(),
     };
     f.set_cursor(
-        active_input_area.x + app.login_window.get_cursor_position() as u16 + 1,
+        active_input_area.x + app.login_window.get_cursor_column() as u16 - get_input_scroll(app, true, active_input_area) + 1,
         // Move one line down, from the border to the input line
         active_input_area.y + 1,
     )
 }
 
+/// Only the active input is scrolled, so that the cursor stays visible in a long input
+fn get_input_scroll(app: &App, is_active: bool, input_area: Rect) -> u16 {
+    if !is_active {
+        return 0;
+    }
+    // -2 because of the borders
+    helpers::horizontal_scroll(app.login_window.get_cursor_column(), input_area.width.saturating_sub(2) as usize) as u16
+}
+
 fn create_login_area(r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -1,142 +1,392 @@
+use std::collections::HashSet;
+use std::ops::Range;
 use ratatui::Frame;
-use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Margin, Rect};
 use ratatui::prelude::{Color, Line, Modifier, Span, Style, Stylize};
-use ratatui::widgets::{Block, Borders, BorderType, List, ListDirection, ListItem, Padding, Paragraph, Wrap};
+use ratatui::widgets::{Block, Borders, BorderType, List, ListDirection, ListItem, ListState, Padding, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Tabs, Wrap, Clear};
 use crate::app::App;
-use crate::chat::{Chat, Message};
-use crate::constants::THEME;
+use crate::chat::{Chat, ChatFilter, DeliveryState, Message, MessageSearchHit, MENTION_PREFIX};
+use crate::chat::manager::ChatManager;
+use crate::theme::Theme;
 use crate::helpers;
-use crate::window::main::ActiveInputEntity;
+use crate::helpers::types::TextInput;
+use crate::i18n::Strings;
+use crate::settings::Settings;
+use crate::window::main::{ActiveInputEntity, MessageView, SearchMode};
+use crate::window::vim::VimMode;
+
+/// The message input grows with its content up to this many lines
+const MAX_INPUT_LINES: usize = 8;
 
 pub fn render_main(app: &mut App, f: &mut Frame) {
     let (main_area, footer_area) = create_main_and_footer(f);
-    let (chats_area, messages_area) = create_chats_and_messages_areas(main_area);
-    let (search_area, chats_area) = create_search_and_chats_area(chats_area);
+    let (chats_area, messages_area) = create_chats_and_messages_areas(main_area, app.settings().chat_list_width);
+    let (search_area, filters_area, chats_area) = create_search_and_chats_area(chats_area);
+    app.main_window.screen_areas.search = search_area;
+    app.main_window.screen_areas.filters = filters_area;
+    app.main_window.screen_areas.chats = chats_area;
 
     render_chats_area(app, f, chats_area, search_area);
+    render_filters(app, f, filters_area);
     render_message_area(app, f, messages_area);
     render_footer(app, f, footer_area);
 }
 
 fn render_chats_area(app: &mut App, f: &mut Frame, chats_area: Rect, search_area: Rect) {
+    let theme = app.settings().theme;
+    let strings = app.strings();
     let is_active = app.main_window.get_active_input_entity() == ActiveInputEntity::SearchChats || app.main_window.get_active_input_entity() == ActiveInputEntity::SelectChat;
     let fg_color = if is_active {
-        THEME.fg
+        theme.fg
+    } else {
+        theme.inactive
+    };
+    let search_mode = app.main_window.get_search_mode();
+    let search_input_value = app.main_window.get_search_input();
+    let is_searching = app.main_window.get_active_input_entity() == ActiveInputEntity::SearchChats;
+    let cursor_column = if is_searching {
+        helpers::input_width(&search_input_value, 0..app.main_window.get_cursor_position())
     } else {
-        THEME.inactive
+        0
     };
-    let search_input_value = helpers::input_to_string(&app.main_window.get_search_input());
-    let search_input = Paragraph::new(search_input_value.as_str())
+    // -2 because of the borders
+    let search_scroll = helpers::horizontal_scroll(cursor_column, search_area.width.saturating_sub(2) as usize);
+    let search_input = Paragraph::new(helpers::input_to_string(&search_input_value))
+        .scroll((0, search_scroll as u16))
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Plain)
-                .title("Пошук")
+                .title(match search_mode {
+                    SearchMode::Users => strings.search,
+                    SearchMode::Messages => strings.search_messages,
+                })
                 .style(Style::default().fg(fg_color))
         );
 
-    let chats = app.main_window.chat_manager.get_active_chats_mut();
     f.render_widget(search_input, search_area);
-    f.render_stateful_widget(
-        build_chats(
-            &chats.items,
-            chats_area,
-            is_active,
-        ),
-        chats_area,
-        &mut chats.state,
-    );
+    match search_mode {
+        SearchMode::Users => {
+            let draft_chat_ids = app.main_window.get_draft_chat_ids();
+            let title = get_chats_title(&app.main_window.chat_manager, strings);
+            let (settings, main_window) = app.settings_and_main_window_mut();
+            let chats = main_window.chat_manager.get_active_chats_mut();
+            f.render_stateful_widget(
+                build_chats(
+                    &chats.items,
+                    &draft_chat_ids,
+                    title,
+                    chats_area,
+                    is_active,
+                    settings,
+                    strings,
+                ),
+                chats_area,
+                &mut chats.state,
+            );
+        }
+        SearchMode::Messages => {
+            let hits = app.main_window.chat_manager.get_message_search_results_mut();
+            f.render_stateful_widget(
+                build_message_search_hits(&hits.items, is_active, &theme, strings),
+                chats_area,
+                &mut hits.state,
+            );
+        }
+    }
 
-    if app.main_window.get_active_input_entity() == ActiveInputEntity::SearchChats {
+    if is_searching {
         f.set_cursor(
-            search_area.x + app.main_window.get_cursor_position() as u16 + 1,
+            search_area.x + (cursor_column - search_scroll) as u16 + 1,
             // Move one line down, from the border to the input line
             search_area.y + 1,
         )
     }
 }
 
-fn render_message_area(app: &App, f: &mut Frame, messages_area: Rect) {
-    let is_active = app.main_window.get_active_input_entity() == ActiveInputEntity::EnterMessage;
+fn render_message_area(app: &mut App, f: &mut Frame, messages_area: Rect) {
+    let theme = app.settings().theme;
+    let strings = app.strings();
+    let message_view = app.main_window.message_view.clone();
+    let is_searching = app.main_window.get_active_input_entity() == ActiveInputEntity::SearchMessages;
+    let is_searching_sent = app.main_window.get_active_input_entity() == ActiveInputEntity::SearchSentMessages;
+    let is_active = app.main_window.get_active_input_entity() == ActiveInputEntity::EnterMessage || is_searching || is_searching_sent;
     let fg_color = if is_active {
-        THEME.fg
+        theme.fg
+    } else {
+        theme.inactive
+    };
+    let sent_search_label = format!("{}: ", strings.search_sent_messages);
+    let query_input = app.main_window.get_sent_message_search_input();
+    let (input, input_title) = if is_searching {
+        (app.main_window.get_chat_search_input(), strings.find_in_chat.to_string())
+    } else if is_searching_sent {
+        let query = helpers::input_to_string(&query_input);
+        let found = app.main_window.get_sent_message_search_match().map(|message| helpers::string_to_input(message));
+        (found.unwrap_or_default(), format!("{}{}", sent_search_label, query))
+    } else if app.settings().vim && app.main_window.get_active_input_entity() == ActiveInputEntity::EnterMessage {
+        let mode = match app.main_window.vim.mode {
+            VimMode::Insert => strings.vim_insert,
+            VimMode::Normal => strings.vim_normal,
+            VimMode::Visual { .. } => strings.vim_visual,
+        };
+        (app.main_window.get_message(), mode.to_string())
     } else {
-        THEME.inactive
+        (app.main_window.get_message(), String::new())
     };
-    let (message_list_area, message_input_area) = create_message_area(messages_area);
+    let selection = app.main_window.get_visual_selection().filter(|_| !is_searching);
+    // -2 because of the borders
+    let input_lines = helpers::wrap_input(&input, messages_area.width.saturating_sub(2) as usize);
+    // the cursor of the sent message search is in the query shown in the title
+    let input_cursor = if is_searching_sent { 0 } else { app.main_window.get_cursor_position() };
+    let (cursor_row, cursor_column) = helpers::cursor_in_lines(&input, &input_lines, input_cursor);
+    let input_height = input_lines.len().min(MAX_INPUT_LINES) as u16 + 2;
+    let (message_list_area, message_input_area) = create_message_area(messages_area, input_height);
+    app.main_window.screen_areas.messages = message_list_area;
+    app.main_window.screen_areas.message_input = message_input_area;
 
     if let Some(loaded_chat) = app.main_window.chat_manager.get_loaded_chat() {
-        let message_input_value = helpers::input_to_string(&app.main_window.get_message());
-        let message_paragraph = Paragraph::new(message_input_value.as_str())
+        let message_input_value = helpers::input_to_string(&input);
+        // keep the cursor line visible when the input is higher than the box
+        let input_scroll = cursor_row.saturating_sub(MAX_INPUT_LINES - 1);
+        let message_paragraph = Paragraph::new(
+            input_lines
+                .iter()
+                .map(|line| build_input_line(&input, line.clone(), selection.as_ref()))
+                .collect::<Vec<Line>>()
+        )
+            .scroll((input_scroll as u16, 0))
             .block(
                 Block::default()
                     .borders(Borders::ALL)
                     .border_type(BorderType::Plain)
+                    .title(input_title)
                     .style(Style::default().fg(fg_color))
             );
+        let messages_title = get_messages_title(loaded_chat, strings);
+        let title = match message_view.current_match {
+            Some(current_match) => format!("{} · {}/{}", messages_title, current_match + 1, message_view.matches.len()),
+            None if is_searching && !message_input_value.is_empty() => format!("{} · 0/0", messages_title),
+            None => messages_title,
+        };
 
-        let messages = if loaded_chat.id.is_none() {
-            vec![]
-        } else {
-            app.main_window.chat_manager.get_messages(loaded_chat.id.unwrap()).clone()
+        let messages = match loaded_chat.id {
+            Some(chat_id) => app.main_window.chat_manager.get_messages(chat_id).clone(),
+            None => vec![],
         };
-        f.render_widget(
-            build_messages(messages, fg_color, &message_list_area),
+        let (mut items, focused_line) = build_messages(messages, &message_view, &message_list_area, app.settings(), strings);
+        // -2 because of the borders
+        let height = message_list_area.height.saturating_sub(2) as usize;
+        let line_count = items.len();
+        app.main_window.set_message_layout(line_count, height, focused_line);
+        let top_line = app.main_window.get_message_top_line();
+        // short histories stick to the bottom like the newest messages do
+        if line_count < height {
+            items.splice(0..0, (line_count..height).map(|_| ListItem::new("")));
+        }
+
+        let message_list = List::new(items)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .style(Style::default().fg(fg_color));
+        f.render_stateful_widget(
+            message_list,
             message_list_area,
+            &mut ListState::default().with_offset(top_line),
+        );
+        let mut scrollbar_state = ScrollbarState::new(line_count.saturating_sub(height)).position(top_line);
+        f.render_stateful_widget(
+            Scrollbar::new(ScrollbarOrientation::VerticalRight).begin_symbol(None).end_symbol(None),
+            message_list_area.inner(&Margin { vertical: 1, horizontal: 0 }),
+            &mut scrollbar_state,
         );
         f.render_widget(message_paragraph, message_input_area);
+        let mention_suggestions = app.main_window.get_mention_suggestions();
+        if !mention_suggestions.is_empty() {
+            render_mention_suggestions(f, message_input_area, mention_suggestions, app.main_window.get_mention_selection(), &theme);
+        }
+        if message_view.new_messages > 0 {
+            render_new_messages_indicator(f, message_list_area, message_view.new_messages, &theme, strings);
+        }
+        if app.main_window.is_confirming_send() {
+            render_send_confirmation(f, message_list_area, input.len(), &theme, strings);
+        }
 
-        if app.main_window.get_active_input_entity() == ActiveInputEntity::EnterMessage {
+        if is_searching_sent {
             f.set_cursor(
-                message_input_area.x + app.main_window.get_cursor_position() as u16 + 1,
-                // Move one line down, from the border to the input line
-                message_input_area.y + 1,
+                message_input_area.x + (helpers::text_width(&sent_search_label) + helpers::input_width(&query_input, 0..app.main_window.get_cursor_position())) as u16 + 1,
+                message_input_area.y,
+            )
+        } else if is_active {
+            f.set_cursor(
+                message_input_area.x + cursor_column as u16 + 1,
+                // Move one line down, from the border to the input lines
+                message_input_area.y + (cursor_row - input_scroll) as u16 + 1,
             )
         }
     } else {
         f.render_widget(
-            get_chat_hints(fg_color),
+            get_chat_hints(fg_color, strings),
             messages_area,
         );
     }
 }
 
-fn create_search_and_chats_area(chats_area: Rect) -> (Rect, Rect) {
+fn render_filters(app: &mut App, f: &mut Frame, filters_area: Rect) {
+    let theme = app.settings().theme;
+    let strings = app.strings();
+    let chat_manager = &app.main_window.chat_manager;
+    let filters = chat_manager.get_filters();
+    let titles = filters.iter().map(|filter| {
+        let name = match filter {
+            ChatFilter::All => strings.filter_all,
+            ChatFilter::Unread => strings.filter_unread,
+            ChatFilter::Direct => strings.filter_direct,
+            ChatFilter::Groups => strings.filter_groups,
+            ChatFilter::Folder(index) => chat_manager.get_folders()[*index].name.as_str(),
+        };
+        match chat_manager.get_filter_unread_total(*filter) {
+            0 => name.to_string(),
+            unread_total => format!("{} ({})", name, unread_total),
+        }
+    }).collect::<Vec<String>>();
+    let selected = filters.iter().position(|filter| *filter == chat_manager.get_filter()).unwrap_or(0);
+    let tabs = Tabs::new(titles.clone())
+        .style(Style::default().fg(theme.inactive))
+        .highlight_style(theme.highlight(theme.active))
+        .select(selected);
+
+    // a tab is its title padded with a space on both sides, the tabs are separated by a divider
+    let mut x = filters_area.x;
+    let filter_tabs = titles
+        .iter()
+        .map(|title| {
+            let start = x;
+            x += Line::from(title.as_str()).width() as u16 + 2;
+            let tab = start..x;
+            x += 1;
+            tab
+        })
+        .collect();
+    app.main_window.screen_areas.filter_tabs = filter_tabs;
+    f.render_widget(tabs, filters_area);
+}
+
+/// Autocomplete list of @mentions right above the message input
+fn render_mention_suggestions(f: &mut Frame, message_input_area: Rect, suggestions: Vec<String>, selection: usize, theme: &Theme) {
+    let width = suggestions.iter().map(|username| helpers::text_width(username) as u16).max().unwrap_or(0) + 3;
+    let height = suggestions.len() as u16 + 2;
+    let area = Rect {
+        x: message_input_area.x,
+        y: message_input_area.y.saturating_sub(height),
+        width: width.min(message_input_area.width),
+        height,
+    };
+    let items: Vec<ListItem> = suggestions
+        .into_iter()
+        .map(|username| ListItem::new(format!("{}{}", MENTION_PREFIX, username)))
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).border_type(BorderType::Plain))
+        .style(Style::default().fg(theme.fg).bg(theme.bg))
+        .highlight_style(theme.highlight(theme.active));
+
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut ListState::default().with_selected(Some(selection)));
+}
+
+/// Floating "↓ N new" label in the bottom right corner of the message list
+fn render_new_messages_indicator(f: &mut Frame, message_list_area: Rect, new_messages: usize, theme: &Theme, strings: &Strings) {
+    let text = format!(" ↓ {} {} ", new_messages, strings.new_messages_short);
+    let width = (helpers::text_width(&text) as u16).min(message_list_area.width.saturating_sub(2));
+    let area = Rect {
+        x: message_list_area.right().saturating_sub(width + 1),
+        y: message_list_area.bottom().saturating_sub(2),
+        width,
+        height: 1,
+    };
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(text).style(theme.highlight(theme.active)), area);
+}
+
+/// Asks to confirm sending a message with a large pasted text, shown at the bottom of the message list
+fn render_send_confirmation(f: &mut Frame, message_list_area: Rect, message_length: usize, theme: &Theme, strings: &Strings) {
+    let text = format!("{} {}", message_length, strings.confirm_large_paste);
+    let width = (helpers::text_width(&text) as u16 + 4).min(message_list_area.width.saturating_sub(2));
+    let area = Rect {
+        x: message_list_area.x + (message_list_area.width.saturating_sub(width)) / 2,
+        y: message_list_area.bottom().saturating_sub(4),
+        width,
+        height: 3,
+    };
+    let paragraph = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .block(Block::default().title(strings.large_message).borders(Borders::ALL).border_type(BorderType::Plain))
+        .style(Style::default().fg(theme.fg).bg(theme.bg));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+fn create_search_and_chats_area(chats_area: Rect) -> (Rect, Rect, Rect) {
     let chats_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
+            Constraint::Length(1),
             Constraint::Min(1),
         ])
         .split(chats_area);
-    (chats_layout[0], chats_layout[1])
+    (chats_layout[0], chats_layout[1], chats_layout[2])
 }
 
-fn create_chats_and_messages_areas(main_area: Rect) -> (Rect, Rect) {
+fn create_chats_and_messages_areas(main_area: Rect, chat_list_width: u16) -> (Rect, Rect) {
     let main_layout = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
-            Constraint::Percentage(25),
-            Constraint::Percentage(75),
+            Constraint::Percentage(chat_list_width),
+            Constraint::Percentage(100 - chat_list_width),
         ])
         .split(main_area);
 
     (main_layout[0], main_layout[1])
 }
 
-fn create_message_area(main_area: Rect) -> (Rect, Rect) {
+fn create_message_area(main_area: Rect, input_height: u16) -> (Rect, Rect) {
     let main_layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Min(3),
-            Constraint::Length(3),
+            Constraint::Length(input_height),
         ])
         .split(main_area);
 
     (main_layout[0], main_layout[1])
 }
 
+/// Line of the message input, the part selected in the vim visual mode is reversed
+fn build_input_line<'a>(input: &TextInput, line: Range<usize>, selection: Option<&Range<usize>>) -> Line<'a> {
+    let Some(selection) = selection else {
+        return Line::from(helpers::input_range_to_string(input, line));
+    };
+    let start = selection.start.clamp(line.start, line.end);
+    let end = selection.end.clamp(line.start, line.end);
+    Line::from(vec![
+        Span::raw(helpers::input_range_to_string(input, line.start..start)),
+        Span::raw(helpers::input_range_to_string(input, start..end)).reversed(),
+        Span::raw(helpers::input_range_to_string(input, end..line.end)),
+    ])
+}
+
 fn render_footer(app: &App, f: &mut Frame, footer_area: Rect) {
+    if app.main_window.get_active_input_entity() == ActiveInputEntity::CommandLine {
+        let command_input = app.main_window.get_command_input();
+        let command = helpers::input_to_string(&command_input);
+        // +1 because of the ':' prompt
+        let cursor_column = helpers::input_width(&command_input, 0..app.main_window.get_cursor_position()) + 1;
+        let scroll = helpers::horizontal_scroll(cursor_column, footer_area.width as usize);
+        f.render_widget(Paragraph::new(format!(":{}", command)).scroll((0, scroll as u16)), footer_area);
+        f.set_cursor(footer_area.x + (cursor_column - scroll) as u16, footer_area.y);
+        return;
+    }
     f.render_widget(
         get_app_hints(app),
         footer_area,
@@ -155,29 +405,70 @@ fn create_main_and_footer(f: &mut Frame) -> (Rect, Rect) {
     (terminal_layout[0], terminal_layout[1])
 }
 
-fn build_chats(chats: &[Chat], chats_area: Rect, is_active: bool) -> List {
+fn get_chats_title(chat_manager: &ChatManager, strings: &Strings) -> String {
+    if chat_manager.is_archived_view() {
+        return strings.archived.to_string();
+    }
+    let title = match chat_manager.get_unread_total() {
+        0 => strings.chats.to_string(),
+        unread_total => format!("{} (+{})", strings.chats, unread_total),
+    };
+    match chat_manager.get_archived_chats_count() {
+        0 => title,
+        count => format!("{} ({}: {})", title, strings.archived, count),
+    }
+}
+
+fn build_chats<'a>(chats: &'a [Chat], draft_chat_ids: &HashSet<String>, title: String, chats_area: Rect, is_active: bool, settings: &Settings, strings: &Strings) -> List<'a> {
+    let theme = settings.theme;
+    let now = helpers::now_timestamp();
     let items: Vec<ListItem> = chats
         .iter()
         .map(|chat| {
-            // let name = chat.name.clone();
-            // let created_at = chat.last_message.as_ref().map(|message| message.created_at.to_string()).unwrap_or_else(|| "".to_string());
-            // let total_width = chats_area.width as usize;
-            // -2 because 1 cell goes for the border at each side
-            // let space_count = total_width - name.len() - created_at.len() - 2;
-            // let mut formatted_string = format!("{name:<0$}{created_at}", space_count + name.len(), name = name, created_at = created_at);
-
+            let pin_marker = if chat.pin_position.is_some() { "⚑ " } else { "" };
             let name = chat.name.clone();
+            let draft_marker = if draft_chat_ids.contains(&chat.internal_id) {
+                format!(" [{}]", strings.draft)
+            } else {
+                "".to_string()
+            };
             let unread_count = if chat.number_of_unread_messages > 0 {
-                format!("(+{})", chat.number_of_unread_messages)
+                format!(" (+{})", chat.number_of_unread_messages)
+            } else {
+                "".to_string()
+            };
+            let mention_count = if chat.number_of_unread_mentions > 0 {
+                format!(" {}{}", MENTION_PREFIX, chat.number_of_unread_mentions)
             } else {
                 "".to_string()
             };
+            let created_at = chat
+                .last_message
+                .as_ref()
+                .map(|message| helpers::time::format_chat_time(message.created_at, now, &settings.time, strings))
+                .unwrap_or_default();
             let total_width = chats_area.width as usize;
-            // -2 because 1 cell goes for the border at each side
-            let space_count = total_width - name.len() - unread_count.len() - 2;
-            let formatted_string = format!("{name:<0$}{unread_count}", space_count + name.len(), name = name, unread_count = unread_count);
+            // -2 because 1 cell goes for the border at each side, wide characters of the name take two cells
+            let space_count = total_width.saturating_sub(
+                [pin_marker, &name, &draft_marker, &created_at, &mention_count, &unread_count]
+                    .iter()
+                    .map(|part| helpers::text_width(part))
+                    .sum::<usize>()
+                    + 2
+            );
             let message_dt = Line::from(vec![
-                Span::from(formatted_string),
+                Span::from(pin_marker),
+                Span::from(name),
+                Span::from(draft_marker).italic(),
+                Span::from(" ".repeat(space_count)),
+                Span::from(created_at).fg(theme.inactive),
+                // mentions stand out even in muted chats
+                Span::from(mention_count).fg(theme.active).bold(),
+                if chat.is_muted {
+                    Span::from(unread_count).fg(theme.inactive)
+                } else {
+                    Span::from(unread_count)
+                },
             ]);
 
             ListItem::new(message_dt)
@@ -185,28 +476,46 @@ fn build_chats(chats: &[Chat], chats_area: Rect, is_active: bool) -> List {
         .collect();
 
     let fg_color = if is_active {
-        THEME.fg
+        theme.fg
     } else {
-        Color::DarkGray
+        theme.dimmed
     };
     List::new(items)
-        .block(Block::default().title("Чати").borders(Borders::ALL).border_type(BorderType::Plain))
+        .block(Block::default().title(title).borders(Borders::ALL).border_type(BorderType::Plain))
         .style(Style::default().fg(fg_color))
-        .highlight_style(Style::default().bg(fg_color).bold().black())
+        .highlight_style(theme.highlight(fg_color))
         .direction(ListDirection::TopToBottom)
 }
 
 fn get_app_hints<'a>(app: &App) -> Paragraph<'a> {
+    let theme = app.settings().theme;
+    let strings = app.strings();
+    if let Some(status) = app.get_status() {
+        return Paragraph::new(status.text.clone())
+            .style(Style::default().fg(if status.is_error { theme.error } else { theme.fg }))
+            .alignment(Alignment::Center);
+    }
+
     let paragraph = match app.is_authenticated() {
         true => {
             if app.main_window.get_active_input_entity() == ActiveInputEntity::EnterMessage {
-                Paragraph::new("`Enter` - відправити повідомлення, 'Esc' - закрити чат, `Ctrl-C` - закрити застосунок")
+                match app.main_window.vim.mode {
+                    VimMode::Normal if app.settings().vim => Paragraph::new(strings.hint_vim_normal),
+                    VimMode::Visual { .. } if app.settings().vim => Paragraph::new(strings.hint_vim_visual),
+                    _ => Paragraph::new(strings.hint_enter_message),
+                }
+            } else if app.main_window.get_active_input_entity() == ActiveInputEntity::SearchMessages {
+                Paragraph::new(strings.hint_chat_search)
+            } else if app.main_window.get_active_input_entity() == ActiveInputEntity::SearchSentMessages {
+                Paragraph::new(strings.hint_sent_message_search)
+            } else if app.main_window.get_active_input_entity() == ActiveInputEntity::SearchChats {
+                Paragraph::new(strings.hint_search)
             } else {
-                Paragraph::new("Натисніть `Ctrl-C` щоб закрити застосунок")
+                Paragraph::new(strings.hint_default)
             }
         }
         false => {
-            Paragraph::new("`Enter` - відправити, `Tab` - наступне поле вводу, `Стрілки праворуч/ліворуч` - переключитись між вкладками")
+            Paragraph::new(strings.hint_login)
         }
     };
 
@@ -215,61 +524,196 @@ fn get_app_hints<'a>(app: &App) -> Paragraph<'a> {
             Block::default()
                 .title_alignment(Alignment::Center),
         )
-        .style(Style::default().fg(THEME.fg))
+        .style(Style::default().fg(theme.fg))
         .alignment(Alignment::Center)
 }
 
-fn build_messages<'a>(messages: Vec<Message>, fg_color: Color, area: &Rect) -> List<'a> {
+/// Lines of the message history from the oldest to the newest and the first line of the focused message
+fn build_messages<'a>(messages: Vec<Message>, message_view: &MessageView, area: &Rect, settings: &Settings, strings: &Strings) -> (Vec<ListItem<'a>>, Option<usize>) {
+    let theme = settings.theme;
+    let now = helpers::now_timestamp();
+    let time_labels: Vec<String> = messages
+        .iter()
+        .map(|message| helpers::time::format_message_time(message.created_at, now, &settings.time, strings))
+        .collect();
+    // times are shown in a column on the left, the message text is wrapped to the rest of the width
+    let time_width = time_labels.iter().map(|label| helpers::text_width(label)).max().unwrap_or(0) + 1;
+    let text_width = (area.width as usize).saturating_sub(time_width + 2).max(1);
+
     let mut items: Vec<ListItem> = vec![];
     let mut sender_username = None;
+    let mut focused_item = None;
 
-    for message in messages.iter() {
-        if sender_username.is_none() || sender_username.clone().unwrap() != message.sender_username {
+    for (i, message) in messages.iter().enumerate() {
+        let is_new_day = i == 0 || !helpers::time::is_same_day(messages[i - 1].created_at, message.created_at, &settings.time);
+        if is_new_day {
+            let day = helpers::time::format_day(message.created_at, now, &settings.time, strings);
+            items.push(ListItem::new(""));
+            items.push(ListItem::new(Line::from(format!("— {} —", day)).alignment(Alignment::Center)).fg(theme.inactive));
+            sender_username = None;
+        }
+        if message_view.first_unread == Some(i) {
+            items.push(ListItem::new(""));
+            items.push(ListItem::new(Line::from(format!("── {} ──", strings.new_messages)).alignment(Alignment::Center)).italic());
+            if message_view.focused_message == Some(i) {
+                focused_item = Some(items.len() - 1);
+            }
+            // start a new group after the divider so the sender is shown again
+            sender_username = None;
+        }
+
+        let wrapped_lines: Vec<String> = if sender_username.is_none() || sender_username.clone().unwrap() != message.sender_username {
             sender_username = Some(message.sender_username.clone());
             let s = &format!(
                 "{}: {}",
                 sender_username.clone().unwrap(),
                 message.text.clone(),
             );
-            let wrapped_strings = textwrap::wrap(s, area.width as usize);
 
             items.push(ListItem::new(""));
-            let mut is_first = true;
-            for string in wrapped_strings {
-                if is_first {
-                    items.push(ListItem::new(string.to_string()));
-                    is_first = false;
-                } else {
-                    items.push(ListItem::new(format_with_spaces(&mut sender_username, string.to_string())));
-                }
+            if message_view.focused_message == Some(i) && focused_item.is_none() {
+                focused_item = Some(items.len());
             }
+            textwrap::wrap(s, text_width)
+                .into_iter()
+                .enumerate()
+                .map(|(line_index, string)| if line_index == 0 {
+                    string.to_string()
+                } else {
+                    format_with_spaces(&sender_username, string.to_string())
+                })
+                .collect()
         } else {
-            let s = message.text.clone();
-            let wrapped_strings = textwrap::wrap(&s, area.width as usize);
-            for string in wrapped_strings {
-                items.push(ListItem::new(format_with_spaces(&mut sender_username, string.to_string())));
+            if message_view.focused_message == Some(i) {
+                focused_item = Some(items.len());
+            }
+            textwrap::wrap(&message.text, text_width)
+                .into_iter()
+                .map(|string| format_with_spaces(&sender_username, string.to_string()))
+                .collect()
+        };
+
+        for (line_index, line) in wrapped_lines.into_iter().enumerate() {
+            let time_label = if line_index == 0 { time_labels[i].as_str() } else { "" };
+            // padded by the display width, `format!` pads by the number of chars
+            let padding = " ".repeat(time_width - helpers::text_width(time_label));
+            let mut spans = vec![Span::from(format!("{}{}", time_label, padding)).fg(theme.inactive)];
+            spans.extend(highlight_terms(line, &message_view.highlighted_terms).spans);
+            let mut item = ListItem::new(Line::from(spans));
+            if message.mentions_current_user {
+                item = item.fg(theme.active);
+            }
+            if message_view.focused_message == Some(i) {
+                item = item.add_modifier(Modifier::BOLD);
+            }
+            items.push(item);
+        }
+        let status_indent = " ".repeat(time_width);
+        match message.delivery_state {
+            DeliveryState::Sent => {}
+            DeliveryState::Pending(_) => {
+                items.push(ListItem::new(status_indent + &format_with_spaces(&sender_username, strings.sending.to_string())).fg(theme.inactive).italic());
+            }
+            DeliveryState::Failed => {
+                items.push(ListItem::new(status_indent + &format_with_spaces(&sender_username, strings.not_sent.to_string())).fg(theme.error));
             }
         }
     }
 
-    items.reverse();
+    (items, focused_item)
+}
+
+fn build_message_search_hits<'a>(hits: &[MessageSearchHit], is_active: bool, theme: &Theme, strings: &Strings) -> List<'a> {
+    let items: Vec<ListItem> = hits
+        .iter()
+        .map(|hit| {
+            ListItem::new(vec![
+                Line::from(vec![
+                    Span::from(hit.chat_name.clone()).bold(),
+                    Span::from(format!(" · {} · {}", hit.sender_username, helpers::format_timestamp(hit.created_at))),
+                ]),
+                Line::from(hit.text.clone()),
+            ])
+        })
+        .collect();
 
+    let fg_color = if is_active {
+        theme.fg
+    } else {
+        theme.dimmed
+    };
     List::new(items)
-        .block(
-            Block::default().title("Повідомлення").borders(Borders::ALL))
+        .block(Block::default().title(format!("{}: {}", strings.found, hits.len())).borders(Borders::ALL).border_type(BorderType::Plain))
         .style(Style::default().fg(fg_color))
-        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
-        .direction(ListDirection::BottomToTop)
+        .highlight_style(theme.highlight(fg_color))
+        .direction(ListDirection::TopToBottom)
+}
+
+/// Splits the text into spans so that every case-insensitive occurrence of the terms is highlighted
+fn highlight_terms<'a>(text: String, terms: &[String]) -> Line<'a> {
+    if terms.is_empty() {
+        return Line::from(text);
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut is_highlighted = vec![false; chars.len()];
+    for term in terms {
+        let term: Vec<char> = term.chars().collect();
+        if term.is_empty() || term.len() > chars.len() {
+            continue;
+        }
+        for start in 0..=chars.len() - term.len() {
+            let matches = chars[start..start + term.len()]
+                .iter()
+                .zip(term.iter())
+                .all(|(c, t)| c.to_lowercase().eq(t.to_lowercase()));
+            if matches {
+                is_highlighted[start..start + term.len()].iter_mut().for_each(|h| *h = true);
+            }
+        }
+    }
+
+    let mut spans = vec![];
+    let mut current = String::new();
+    let mut current_is_highlighted = false;
+    for (c, highlighted) in chars.into_iter().zip(is_highlighted) {
+        if highlighted != current_is_highlighted && !current.is_empty() {
+            spans.push(styled_span(std::mem::take(&mut current), current_is_highlighted));
+        }
+        current_is_highlighted = highlighted;
+        current.push(c);
+    }
+    if !current.is_empty() {
+        spans.push(styled_span(current, current_is_highlighted));
+    }
+
+    Line::from(spans)
+}
+
+fn styled_span<'a>(text: String, is_highlighted: bool) -> Span<'a> {
+    if is_highlighted {
+        Span::from(text).style(Style::default().add_modifier(Modifier::REVERSED))
+    } else {
+        Span::from(text)
+    }
+}
+
+/// The id of the chat is shown for the folders in the settings, they list the chats by id
+fn get_messages_title(chat: &Chat, strings: &Strings) -> String {
+    match chat.id {
+        Some(chat_id) => format!("{} · #{}", strings.messages, chat_id),
+        None => strings.messages.to_string(),
+    }
 }
 
 fn format_with_spaces(sender_username: &Option<String>, message_text: String) -> String {
-    let spaces_count = sender_username.as_ref().map_or(0, |name| name.len() + 2);
+    let spaces_count = sender_username.as_ref().map_or(0, |name| helpers::text_width(name) + 2);
     let spaces = " ".repeat(spaces_count);
     format!("{}{}", spaces, message_text)
 }
 
-fn get_chat_hints<'a>(fg_color: Color) -> Paragraph<'a> {
-    Paragraph::new("Використовуйте стрілки вгору/вниз щоб вибрати чат. Натисніть `Enter`, щоб відкрити чат. Почніть вводити текст щоб знайти користувача та натисніть `Enter`. `Esc` - назад/скинути пошук")
+fn get_chat_hints<'a>(fg_color: Color, strings: &Strings) -> Paragraph<'a> {
+    Paragraph::new(strings.hint_chats)
         .block(
             Block::default()
                 .borders(Borders::ALL)
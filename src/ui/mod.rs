@@ -1,5 +1,7 @@
 use ratatui::{
     prelude::Frame,
+    style::Style,
+    widgets::Block,
 };
 
 use crate::app::App;
@@ -9,6 +11,9 @@ mod main;
 pub mod tui;
 
 pub fn render(app: &mut App, f: &mut Frame) {
+    // the whole screen takes the colours of the theme, so a light theme works in a dark terminal
+    let theme = app.settings().theme;
+    f.render_widget(Block::default().style(Style::default().fg(theme.fg).bg(theme.bg)), f.size());
     main::render_main(app, f);
     if !app.is_authenticated() {
         login::render_login_register(app, f);
@@ -1,21 +1,29 @@
 use std::{io, panic};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use anyhow::Result;
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::{
+        DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
     terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::backend::CrosstermBackend;
+use ratatui::layout::Rect;
 use ratatui::Terminal;
 
 use crate::{app::App, event::EventHandler, ui};
 
 pub type CrosstermTerminal = Terminal<CrosstermBackend<io::Stderr>>;
 
-pub fn build_tui() -> Tui {
+/// The keyboard enhancement flags were pushed and have to be popped when the terminal is reset
+static KEYBOARD_ENHANCED: AtomicBool = AtomicBool::new(false);
+
+pub fn build_tui(tick_rate: u64) -> Tui {
     let backend = CrosstermBackend::new(io::stderr());
     let terminal = Terminal::new(backend).unwrap();
-    let events = EventHandler::new(100);
+    let events = EventHandler::new(tick_rate);
     Tui::new(terminal, events)
 }
 
@@ -39,8 +47,18 @@ impl Tui {
         crossterm::execute!(
             io::stderr(),
             EnterAlternateScreen,
-            EnableMouseCapture
+            EnableMouseCapture,
+            EnableBracketedPaste
         )?;
+        // terminals with the kitty keyboard protocol report Ctrl, Alt and Shift combinations like Shift-Enter
+        // unambiguously, so they can be bound in the keymap
+        if terminal::supports_keyboard_enhancement().unwrap_or(false) {
+            crossterm::execute!(
+                io::stderr(),
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+            )?;
+            KEYBOARD_ENHANCED.store(true, Ordering::Relaxed);
+        }
 
         // Define a custom panic hook to reset the terminal properties.
         // This way, you won't have your terminal messed up if an unexpected error happens.
@@ -56,11 +74,15 @@ impl Tui {
     }
 
     fn reset() -> Result<()> {
+        if KEYBOARD_ENHANCED.swap(false, Ordering::Relaxed) {
+            crossterm::execute!(io::stderr(), PopKeyboardEnhancementFlags)?;
+        }
         terminal::disable_raw_mode()?;
         crossterm::execute!(
             io::stderr(),
             LeaveAlternateScreen,
-            DisableMouseCapture
+            DisableMouseCapture,
+            DisableBracketedPaste
         )?;
         Ok(())
     }
@@ -71,6 +93,11 @@ impl Tui {
         Ok(())
     }
 
+    pub fn resize(&mut self, width: u16, height: u16) -> Result<()> {
+        self.terminal.resize(Rect::new(0, 0, width, height))?;
+        Ok(())
+    }
+
     pub fn draw(&mut self, app: &mut App) -> Result<()> {
         self.terminal.draw(|frame| ui::render(app, frame))?;
         Ok(())
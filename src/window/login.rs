@@ -1,6 +1,9 @@
 use std::collections::HashMap;
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::KeyEvent;
 use strum::{Display, EnumIter, FromRepr};
+use crate::helpers;
+use crate::i18n::Strings;
+use crate::helpers::line_editor::{Edit, LineEditor};
 use crate::helpers::types::TextInput;
 use crate::window::InputEntity;
 
@@ -16,23 +19,16 @@ pub enum LoginActiveInput {
 #[derive(Default, Clone, Copy, Display, FromRepr, EnumIter)]
 pub enum LoginTabs {
     #[default]
-    #[strum(to_string = "Увійти")]
     Login,
-    #[strum(to_string = "Зареєструватися")]
     Register,
 }
 
 impl LoginTabs {
-    fn previous(&self) -> Self {
-        let current_index: usize = *self as usize;
-        let previous_index = current_index.saturating_sub(1);
-        Self::from_repr(previous_index).unwrap_or(*self)
-    }
-
-    fn next(&self) -> Self {
-        let current_index = *self as usize;
-        let next_index = current_index.saturating_add(1);
-        Self::from_repr(next_index).unwrap_or(*self)
+    pub fn title(&self, strings: &Strings) -> &'static str {
+        match self {
+            LoginTabs::Login => strings.login_tab,
+            LoginTabs::Register => strings.register_tab,
+        }
     }
 }
 
@@ -51,7 +47,7 @@ pub struct LoginWindow {
 
     pub active_input_field: LoginActiveInput,
     pub selected_tab: LoginTabs,
-    cursor_position: usize,
+    line_editor: LineEditor,
 
     actual_password_input: TextInput,
     actual_register_password_input: TextInput,
@@ -72,7 +68,7 @@ impl Default for LoginWindow {
 
             active_input_field: LoginActiveInput::Username,
             selected_tab: LoginTabs::Login,
-            cursor_position: 0,
+            line_editor: LineEditor::default(),
             actual_password_input: TextInput::new(),
             actual_register_password_input: TextInput::new(),
             actual_register_password_confirmation_input: TextInput::new(),
@@ -82,76 +78,22 @@ impl Default for LoginWindow {
 
 impl InputEntity for LoginWindow {
     fn process_input(&mut self, key_event: KeyEvent) {
-        match key_event.code {
-            KeyCode::Char(to_insert) => {
-                self.enter_char(to_insert);
-            }
-            KeyCode::Backspace => {
-                self.delete_char();
-            }
-            KeyCode::Left | KeyCode::Right => {
-                // self.move_cursor_left();
-                self.switch_tabs()
-            }
-            // KeyCode::Right => {
-            // self.move_cursor_right();
-            // }
-            KeyCode::Tab => {
-                self.switch_to_next_input();
-            }
-            _ => {}
-        };
-    }
-
-    fn enter_char(&mut self, new_char: char) {
-        let cursor_position = self.cursor_position;
-        match self.active_input_field {
-            LoginActiveInput::Username | LoginActiveInput::RegisterUsername => {
-                self.get_active_ui_input_mut().insert(cursor_position, new_char);
-            }
-            LoginActiveInput::Password | LoginActiveInput::RegisterPassword | LoginActiveInput::RegisterPasswordConfirmation => {
-                self.get_active_input_mut().insert(cursor_position, new_char);
-                self.get_active_ui_input_mut().insert(cursor_position, '*');
-            }
+        let (input, line_editor) = self.get_active_input_and_editor();
+        if line_editor.process(input, key_event) == Edit::TextChanged {
+            self.update_masked_input();
         }
-
-        self.move_cursor_right();
     }
 
-    fn delete_char(&mut self) {
-        if self.cursor_position == 0 {
-            return;
-        }
-
-        let cursor_position = self.cursor_position - 1;
-        match self.active_input_field {
-            LoginActiveInput::Password | LoginActiveInput::RegisterPassword | LoginActiveInput::RegisterPasswordConfirmation => {
-                self.get_active_input_mut().remove(cursor_position);
-                self.get_active_ui_input_mut().remove(cursor_position);
-            }
-            LoginActiveInput::Username | LoginActiveInput::RegisterUsername => {
-                self.get_active_ui_input_mut().remove(cursor_position);
-            }
-        }
-        self.move_cursor_left();
-    }
-
-    fn move_cursor_left(&mut self) {
-        let cursor_moved_left = self.cursor_position.saturating_sub(1);
-        self.cursor_position = self.clamp_cursor(cursor_moved_left);
-    }
-
-    fn move_cursor_right(&mut self) {
-        let cursor_moved_right = self.cursor_position.saturating_add(1);
-        self.cursor_position = self.clamp_cursor(cursor_moved_right);
-    }
-
-    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.get_active_input().len())
+    fn paste(&mut self, text: &str) {
+        let text: String = text.chars().filter(|c| !c.is_control()).collect();
+        let (input, line_editor) = self.get_active_input_and_editor();
+        line_editor.insert_str(input, &text);
+        self.update_masked_input();
     }
 
     fn reset_cursor(&mut self) {
-        self.cursor_position = 0;
+        let (input, line_editor) = self.get_active_input_and_editor();
+        line_editor.set_cursor(input, 0);
     }
 
     fn switch_to_next_input(&mut self) {
@@ -182,8 +124,16 @@ impl InputEntity for LoginWindow {
 }
 
 impl LoginWindow {
-    pub fn get_cursor_position(&self) -> usize {
-        self.cursor_position
+    /// Column of the cursor in cells of the shown input
+    pub fn get_cursor_column(&self) -> usize {
+        let input = match self.active_input_field {
+            LoginActiveInput::Username => &self.username_input,
+            LoginActiveInput::Password => &self.password_input,
+            LoginActiveInput::RegisterUsername => &self.register_username_input,
+            LoginActiveInput::RegisterPassword => &self.register_password_input,
+            LoginActiveInput::RegisterPasswordConfirmation => &self.register_password_confirmation_input,
+        };
+        helpers::input_width(input, 0..self.line_editor.cursor())
     }
 
     pub fn get_input_values(&self) -> HashMap<String, TextInput> {
@@ -223,17 +173,28 @@ impl LoginWindow {
         }
     }
 
-    fn get_active_input_mut(&mut self) -> &mut TextInput {
-        match self.active_input_field {
+    fn get_active_input_and_editor(&mut self) -> (&mut TextInput, &mut LineEditor) {
+        let input = match self.active_input_field {
             LoginActiveInput::Username => &mut self.username_input,
             LoginActiveInput::Password => &mut self.actual_password_input,
             LoginActiveInput::RegisterUsername => &mut self.register_username_input,
             LoginActiveInput::RegisterPassword => &mut self.actual_register_password_input,
             LoginActiveInput::RegisterPasswordConfirmation => &mut self.actual_register_password_confirmation_input,
+        };
+        (input, &mut self.line_editor)
+    }
+
+    /// The password inputs are shown as asterisks, the shown input follows the length of the actual one
+    fn update_masked_input(&mut self) {
+        if let LoginActiveInput::Username | LoginActiveInput::RegisterUsername = self.active_input_field {
+            return;
         }
+        let len = self.get_active_input().len();
+        *self.get_active_ui_input_mut() = std::iter::repeat_n("*".to_string(), len).collect();
     }
 
     fn move_cursor_to_eol(&mut self) {
-        self.cursor_position = self.get_active_input().len();
+        let (input, line_editor) = self.get_active_input_and_editor();
+        line_editor.move_to_end(input);
     }
 }
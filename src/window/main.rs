@@ -1,61 +1,563 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::Rect;
+use crate::chat::MENTION_PREFIX;
 use crate::chat::manager::ChatManager;
-use crate::helpers;
+use crate::{helpers, storage};
+use crate::helpers::line_editor::{Edit, LineEditor};
 use crate::helpers::types::TextInput;
 use crate::window::InputEntity;
+use crate::window::vim::{VimMode, VimState};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ActiveInputEntity {
+    #[default]
     SearchChats,
     SelectChat,
     EnterMessage,
+    /// Search in the messages of the loaded chat
+    SearchMessages,
+    /// The `:` command line of the vim mode
+    CommandLine,
+    /// Reverse search in the sent messages of all chats
+    SearchSentMessages,
 }
 
-impl Default for ActiveInputEntity {
-    fn default() -> Self {
-        ActiveInputEntity::SearchChats
-    }
+/// What the search box is looking for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchMode {
+    #[default]
+    Users,
+    Messages,
+}
+
+/// How the messages of the loaded chat are presented
+#[derive(Debug, Clone, Default)]
+pub struct MessageView {
+    /// Index of the message the view is scrolled to, the view follows the newest message if None
+    pub focused_message: Option<usize>,
+    /// Words to highlight in the messages
+    pub highlighted_terms: Vec<String>,
+    /// Indices of the messages matching the in-chat search, from the oldest to the newest
+    pub matches: Vec<usize>,
+    /// Position in `matches` of the focused match
+    pub current_match: Option<usize>,
+    /// Index of the first message that was unread when the chat was opened, the divider is shown before it
+    pub first_unread: Option<usize>,
+    /// Messages received while the view doesn't follow the newest message
+    pub new_messages: usize,
+    /// The view is scrolled to the focused message until the scroll is settled after the next render
+    pub scroll_to_focused: bool,
+}
+
+/// Scroll position of the message list of a chat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScrollPosition {
+    /// Follows the newest message
+    #[default]
+    Bottom,
+    /// Index of the first visible line, it stays in place when new messages arrive
+    Line(usize),
+}
+
+/// Reverse search in the sent messages, started with Ctrl-R
+#[derive(Debug, Clone, Default)]
+struct SentMessageSearch {
+    /// Sent messages of all chats when the search was started, the newest first
+    sent_messages: Vec<String>,
+    /// Sent messages containing the query, the newest first
+    matches: Vec<String>,
+    current_match: usize,
+}
+
+/// Screen areas of the rendered main window, used to find what the mouse points at
+#[derive(Debug, Clone, Default)]
+pub struct ScreenAreas {
+    pub search: Rect,
+    pub filters: Rect,
+    /// Columns of the filter tabs
+    pub filter_tabs: Vec<Range<u16>>,
+    pub chats: Rect,
+    pub messages: Rect,
+    pub message_input: Rect,
+}
+
+/// Size of the rendered message list, used to scroll by pages and to clamp the scroll position
+#[derive(Debug, Clone, Copy, Default)]
+struct MessageLayout {
+    line_count: usize,
+    height: usize,
+    /// First line of the focused message
+    focused_line: Option<usize>,
 }
 
 #[derive(Default)]
 pub struct MainWindow {
     pub chat_manager: ChatManager,
+    pub message_view: MessageView,
+    pub vim: VimState,
+    pub screen_areas: ScreenAreas,
     search_input: TextInput,
     message_input: TextInput,
+    chat_search_input: TextInput,
+    command_input: TextInput,
+    sent_message_search_input: TextInput,
+    sent_message_search: SentMessageSearch,
+    /// Position in the sent messages of the message recalled into the message input with Up/Down
+    recalled_message: Option<usize>,
+    /// Focus and cursor position to return to when the command line is closed
+    command_return: Option<(ActiveInputEntity, usize)>,
+    /// Unsent messages by chat internal id
+    drafts: HashMap<String, String>,
+    /// Error of the last failed save of the drafts, shown in the status bar by the app
+    save_error: Option<String>,
+    /// Scroll positions of the message lists by chat internal id
+    scroll_positions: HashMap<String, ScrollPosition>,
+    message_layout: MessageLayout,
     active_input_entity: ActiveInputEntity,
-    cursor_position: usize,
+    search_mode: SearchMode,
+    line_editor: LineEditor,
+    /// Selected @mention suggestion
+    mention_selection: usize,
+    /// The suggestions are hidden with Esc until the input changes
+    is_mention_dismissed: bool,
+    /// The message input contains a large pasted text, sending it needs a confirmation
+    has_large_paste: bool,
+    is_confirming_send: bool,
 }
 
+const MAX_MENTION_SUGGESTIONS: usize = 5;
+/// Pasted messages longer than this are sent only after a confirmation
+const LARGE_PASTE_CHARS: usize = 1000;
+const LARGE_PASTE_LINES: usize = 10;
+
 impl MainWindow {
-    pub fn new(chat_manager: ChatManager) -> Self {
+    pub fn new(chat_manager: ChatManager, drafts: HashMap<String, String>) -> Self {
         Self {
             chat_manager,
+            drafts,
             ..Default::default()
         }
     }
 
     pub fn get_cursor_position(&self) -> usize {
-        self.cursor_position
+        self.line_editor.cursor()
     }
 
     pub fn get_active_input(&self) -> String {
         match self.active_input_entity {
             ActiveInputEntity::SearchChats => helpers::input_to_string(&self.search_input),
             ActiveInputEntity::EnterMessage => helpers::input_to_string(&self.message_input),
-            _ => unreachable!("This function must not be called with with current active input entity")
+            ActiveInputEntity::SearchMessages => helpers::input_to_string(&self.chat_search_input),
+            ActiveInputEntity::CommandLine => helpers::input_to_string(&self.command_input),
+            ActiveInputEntity::SearchSentMessages => helpers::input_to_string(&self.sent_message_search_input),
+            _ => unreachable!("This function must not be called with the current active input entity")
         }
     }
 
-    pub fn get_active_input_mut(&mut self) -> &mut TextInput {
-        match self.active_input_entity {
+    fn get_active_input_and_editor(&mut self) -> (&mut TextInput, &mut LineEditor) {
+        let input = match self.active_input_entity {
             ActiveInputEntity::SearchChats => &mut self.search_input,
             ActiveInputEntity::EnterMessage => &mut self.message_input,
-            _ => unreachable!("This function must not be called with with current active input entity")
+            ActiveInputEntity::SearchMessages => &mut self.chat_search_input,
+            ActiveInputEntity::CommandLine => &mut self.command_input,
+            ActiveInputEntity::SearchSentMessages => &mut self.sent_message_search_input,
+            _ => unreachable!("This function must not be called with the current active input entity")
+        };
+        (input, &mut self.line_editor)
+    }
+
+    /// Edits the message input with the line editor, used by the vim mode
+    pub fn edit_message_input<R>(&mut self, edit: impl FnOnce(&mut TextInput, &mut LineEditor) -> R) -> R {
+        edit(&mut self.message_input, &mut self.line_editor)
+    }
+
+    /// Range of the message input selected in the visual mode
+    pub fn get_visual_selection(&self) -> Option<Range<usize>> {
+        let VimMode::Visual { anchor } = self.vim.mode else {
+            return None;
+        };
+        let cursor = self.line_editor.cursor();
+        Some(anchor.min(cursor)..(anchor.max(cursor) + 1).min(self.message_input.len()))
+    }
+
+    /// Puts the next older or newer sent message into the message input. The recall starts from an empty
+    /// input and continues while the input holds the recalled message, going past the newest one clears the input
+    pub fn recall_sent_message(&mut self, sent_messages: &[String], older: bool) {
+        let input = helpers::input_to_string(&self.message_input);
+        let current = match self.recalled_message {
+            Some(i) if sent_messages.get(i) == Some(&input) => Some(i),
+            _ if input.is_empty() => None,
+            _ => return,
+        };
+        let recalled = match (current, older) {
+            (None, true) if !sent_messages.is_empty() => Some(0),
+            (Some(i), true) if i + 1 < sent_messages.len() => Some(i + 1),
+            (Some(i), false) => i.checked_sub(1),
+            _ => return,
+        };
+        self.recalled_message = recalled;
+        self.message_input = recalled.map(|i| helpers::string_to_input(&sent_messages[i])).unwrap_or_default();
+        self.line_editor.move_to_end(&self.message_input);
+    }
+
+    pub fn start_sent_message_search(&mut self, sent_messages: Vec<String>) {
+        self.sent_message_search = SentMessageSearch { sent_messages, ..Default::default() };
+        self.sent_message_search_input.clear();
+        self.set_active_input_entity(ActiveInputEntity::SearchSentMessages);
+        self.update_sent_message_search();
+    }
+
+    fn update_sent_message_search(&mut self) {
+        let query = helpers::input_to_string(&self.sent_message_search_input).to_lowercase();
+        let search = &mut self.sent_message_search;
+        search.matches = search
+            .sent_messages
+            .iter()
+            .filter(|message| message.to_lowercase().contains(&query))
+            .cloned()
+            .collect();
+        search.current_match = 0;
+    }
+
+    /// Ctrl-R again goes to the next older match
+    pub fn select_older_sent_message_match(&mut self) {
+        let search = &mut self.sent_message_search;
+        if search.current_match + 1 < search.matches.len() {
+            search.current_match += 1;
         }
     }
 
+    pub fn get_sent_message_search_match(&self) -> Option<&String> {
+        self.sent_message_search.matches.get(self.sent_message_search.current_match)
+    }
+
+    pub fn get_sent_message_search_input(&self) -> TextInput {
+        self.sent_message_search_input.clone()
+    }
+
+    /// Puts the found message into the message input, the input is kept if nothing was found
+    pub fn accept_sent_message_search(&mut self) {
+        if let Some(message) = self.get_sent_message_search_match() {
+            self.message_input = helpers::string_to_input(message);
+        }
+        self.stop_sent_message_search();
+    }
+
+    fn stop_sent_message_search(&mut self) {
+        self.sent_message_search = SentMessageSearch::default();
+        self.sent_message_search_input.clear();
+        self.set_active_input_entity(ActiveInputEntity::EnterMessage);
+    }
+
+    /// Whether the message has to be confirmed before sending, asks for the confirmation if so
+    pub fn needs_send_confirmation(&mut self) -> bool {
+        if self.has_large_paste {
+            self.is_confirming_send = true;
+        }
+        self.has_large_paste
+    }
+
+    pub fn is_confirming_send(&self) -> bool {
+        self.is_confirming_send
+    }
+
+    pub fn confirm_send(&mut self) {
+        self.is_confirming_send = false;
+        self.has_large_paste = false;
+    }
+
+    pub fn cancel_send(&mut self) {
+        self.is_confirming_send = false;
+    }
+
+    pub fn get_command_input(&self) -> TextInput {
+        self.command_input.clone()
+    }
+
+    pub fn open_command_line(&mut self) {
+        self.command_return = Some((self.active_input_entity, self.line_editor.cursor()));
+        self.command_input.clear();
+        self.set_active_input_entity(ActiveInputEntity::CommandLine);
+    }
+
+    /// Returns the focus to where the command line was opened from
+    pub fn close_command_line(&mut self) {
+        let (active_input_entity, cursor) = self.command_return.take().unwrap_or_default();
+        self.active_input_entity = active_input_entity;
+        if active_input_entity != ActiveInputEntity::SelectChat {
+            let (input, line_editor) = self.get_active_input_and_editor();
+            line_editor.set_cursor(input, cursor);
+        }
+    }
+
+    pub fn enter_char(&mut self, new_char: char) {
+        let (input, line_editor) = self.get_active_input_and_editor();
+        line_editor.insert(input, new_char);
+    }
+
     pub fn get_active_input_entity(&self) -> ActiveInputEntity {
-        self.active_input_entity.clone()
+        self.active_input_entity
+    }
+
+    pub fn get_search_mode(&self) -> SearchMode {
+        self.search_mode
+    }
+
+    pub fn switch_search_mode(&mut self) {
+        self.search_mode = match self.search_mode {
+            SearchMode::Users => SearchMode::Messages,
+            SearchMode::Messages => SearchMode::Users,
+        };
+        self.chat_manager.clear_search_results();
+        self.chat_manager.clear_message_search_results();
+    }
+
+    pub fn focus_message(&mut self, message_index: usize, highlighted_terms: Vec<String>) {
+        self.message_view = MessageView {
+            focused_message: Some(message_index),
+            highlighted_terms,
+            first_unread: self.message_view.first_unread,
+            scroll_to_focused: true,
+            ..Default::default()
+        };
+    }
+
+    /// Shows the unread divider before the first of the last `unread_count` messages
+    /// of the loaded chat and scrolls to it
+    pub fn show_first_unread(&mut self, unread_count: u32) {
+        let Some(chat_id) = self.chat_manager.get_loaded_chat().and_then(|chat| chat.id) else {
+            return;
+        };
+        let first_unread = self.chat_manager.get_messages(chat_id).len().checked_sub(unread_count as usize);
+        if unread_count > 0 && first_unread.is_some() {
+            self.message_view.first_unread = first_unread;
+            self.message_view.focused_message = first_unread;
+            self.message_view.scroll_to_focused = true;
+        }
+    }
+
+    /// Counts a message received in the loaded chat while the user is looking at older messages
+    pub fn note_new_message(&mut self) {
+        if self.get_scroll_position() != ScrollPosition::Bottom {
+            self.message_view.new_messages += 1;
+        }
+    }
+
+    /// Scrolls the view back to the newest message
+    pub fn follow_newest_message(&mut self) {
+        self.message_view = MessageView {
+            first_unread: self.message_view.first_unread,
+            ..Default::default()
+        };
+        self.set_scroll_position(ScrollPosition::Bottom);
+    }
+
+    pub fn get_scroll_position(&self) -> ScrollPosition {
+        self.chat_manager
+            .get_loaded_chat()
+            .and_then(|chat| self.scroll_positions.get(&chat.internal_id))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn set_scroll_position(&mut self, scroll_position: ScrollPosition) {
+        let Some(chat) = self.chat_manager.get_loaded_chat() else {
+            return;
+        };
+        if scroll_position == ScrollPosition::Bottom {
+            self.message_view.new_messages = 0;
+        }
+        self.scroll_positions.insert(chat.internal_id.clone(), scroll_position);
+    }
+
+    /// Remembers the layout of the rendered message list, called by the UI on every render.
+    /// Only this cache is written by the UI, the scroll position is changed by `settle_message_scroll`
+    pub fn set_message_layout(&mut self, line_count: usize, height: usize, focused_line: Option<usize>) {
+        self.message_layout = MessageLayout { line_count, height, focused_line };
+    }
+
+    /// Keeps the scroll to the focused message as the scroll position, called after the message list is rendered
+    pub fn settle_message_scroll(&mut self) {
+        if !self.message_view.scroll_to_focused {
+            return;
+        }
+        let top_line = self.get_message_top_line();
+        let is_laid_out = self.message_layout.focused_line.is_some();
+        self.message_view.scroll_to_focused = false;
+        if is_laid_out {
+            self.scroll_messages_to_line(top_line);
+        }
+    }
+
+    /// Index of the first visible line of the message list
+    pub fn get_message_top_line(&self) -> usize {
+        let max_top_line = self.message_layout.line_count.saturating_sub(self.message_layout.height);
+        if let Some(focused_line) = self.message_layout.focused_line.filter(|_| self.message_view.scroll_to_focused) {
+            // the focused message is shown in the upper third of the list
+            return focused_line.saturating_sub(self.message_layout.height / 3).min(max_top_line);
+        }
+        match self.get_scroll_position() {
+            ScrollPosition::Bottom => max_top_line,
+            ScrollPosition::Line(line) => line.min(max_top_line),
+        }
+    }
+
+    /// Scrolls so the line is at the top, reaching the end of the list follows the newest message again
+    pub fn scroll_messages_to_line(&mut self, line: usize) {
+        self.message_view.scroll_to_focused = false;
+        let max_top_line = self.message_layout.line_count.saturating_sub(self.message_layout.height);
+        if line >= max_top_line {
+            self.set_scroll_position(ScrollPosition::Bottom);
+        } else {
+            self.set_scroll_position(ScrollPosition::Line(line));
+        }
+    }
+
+    pub fn scroll_messages(&mut self, lines: isize) {
+        let top_line = self.get_message_top_line();
+        self.scroll_messages_to_line(top_line.saturating_add_signed(lines));
+    }
+
+    pub fn scroll_messages_by_page(&mut self, up: bool) {
+        let page = self.message_layout.height.saturating_sub(1).max(1) as isize;
+        self.scroll_messages(if up { -page } else { page });
+    }
+
+    /// The word being typed before the cursor if it starts with `@`: its start position and the text after `@`
+    fn get_mention_query(&self) -> Option<(usize, String)> {
+        if self.active_input_entity != ActiveInputEntity::EnterMessage {
+            return None;
+        }
+        let before_cursor: Vec<&String> = self.message_input.iter().take(self.line_editor.cursor()).collect();
+        let start = before_cursor.iter().rposition(|g| g.chars().all(char::is_whitespace)).map_or(0, |i| i + 1);
+        match before_cursor[start..].split_first() {
+            Some((prefix, query)) if prefix.chars().eq([MENTION_PREFIX]) => {
+                Some((start, query.iter().map(|g| g.as_str()).collect()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Usernames of the loaded group chat members matching the `@` word before the cursor
+    pub fn get_mention_suggestions(&self) -> Vec<String> {
+        if self.is_mention_dismissed {
+            return vec![];
+        }
+        let (Some((_, query)), Some(chat)) = (self.get_mention_query(), self.chat_manager.get_loaded_chat()) else {
+            return vec![];
+        };
+        if chat.members.len() <= 2 {
+            return vec![];
+        }
+        let query = query.to_lowercase();
+        let mut suggestions: Vec<String> = chat
+            .members
+            .iter()
+            .map(|member| member.username.clone())
+            .filter(|username| username.to_lowercase().starts_with(&query))
+            .collect();
+        suggestions.sort();
+        suggestions.truncate(MAX_MENTION_SUGGESTIONS);
+        suggestions
+    }
+
+    pub fn get_mention_selection(&self) -> usize {
+        self.mention_selection
+    }
+
+    fn move_mention_selection(&mut self, up: bool) {
+        let count = self.get_mention_suggestions().len();
+        if count == 0 {
+            return;
+        }
+        self.mention_selection = if up {
+            (self.mention_selection + count - 1) % count
+        } else {
+            (self.mention_selection + 1) % count
+        };
+    }
+
+    /// Replaces the `@` word before the cursor with the selected suggestion
+    fn complete_mention(&mut self) {
+        let suggestions = self.get_mention_suggestions();
+        let (Some((start, _)), Some(username)) = (self.get_mention_query(), suggestions.get(self.mention_selection)) else {
+            return;
+        };
+        let mention = format!("{}{} ", MENTION_PREFIX, username);
+        self.line_editor.replace_before_cursor(&mut self.message_input, start, &mention);
+        self.mention_selection = 0;
+    }
+
+    pub fn get_chat_search_input(&self) -> TextInput {
+        self.chat_search_input.clone()
+    }
+
+    /// Starts the search in the messages of the loaded chat
+    pub fn start_chat_search(&mut self) {
+        if self.chat_manager.get_loaded_chat().is_none() {
+            return;
+        }
+        self.chat_search_input.clear();
+        self.follow_newest_message();
+        self.set_active_input_entity(ActiveInputEntity::SearchMessages);
+    }
+
+    fn stop_chat_search(&mut self) {
+        self.chat_search_input.clear();
+        self.follow_newest_message();
+        self.set_active_input_entity(ActiveInputEntity::EnterMessage);
+    }
+
+    /// Finds the messages of the loaded chat containing the search input and focuses the newest one
+    fn update_chat_search(&mut self) {
+        let query = helpers::input_to_string(&self.chat_search_input).to_lowercase();
+        let chat_id = self.chat_manager.get_loaded_chat().and_then(|chat| chat.id);
+        let (Some(chat_id), false) = (chat_id, query.is_empty()) else {
+            self.follow_newest_message();
+            return;
+        };
+
+        let matches: Vec<usize> = self
+            .chat_manager
+            .get_messages(chat_id)
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| message.text.to_lowercase().contains(&query))
+            .map(|(i, _)| i)
+            .collect();
+        let current_match = matches.len().checked_sub(1);
+        self.message_view = MessageView {
+            focused_message: current_match.map(|i| matches[i]),
+            highlighted_terms: vec![query],
+            matches,
+            current_match,
+            first_unread: self.message_view.first_unread,
+            new_messages: 0,
+            scroll_to_focused: true,
+        };
+    }
+
+    /// Focuses the previous (older) or the next (newer) match of the in-chat search, wrapping around
+    pub fn select_chat_search_match(&mut self, older: bool) {
+        let view = &mut self.message_view;
+        let Some(current_match) = view.current_match else {
+            return;
+        };
+        let count = view.matches.len();
+        let current_match = if older {
+            (current_match + count - 1) % count
+        } else {
+            (current_match + 1) % count
+        };
+        view.current_match = Some(current_match);
+        view.focused_message = Some(view.matches[current_match]);
+        view.scroll_to_focused = true;
+    }
+
+    pub fn reset_message_view(&mut self) {
+        self.message_view = MessageView::default();
     }
 
     pub fn get_search_input(&self) -> TextInput {
@@ -69,12 +571,66 @@ impl MainWindow {
     pub fn pop_message_input(&mut self) -> TextInput {
         let message = self.message_input.clone();
         self.message_input.clear();
+        self.has_large_paste = false;
         self.reset_cursor();
+        if let Some(chat) = self.chat_manager.get_loaded_chat() {
+            if self.drafts.remove(&chat.internal_id).is_some() {
+                self.save_drafts();
+            }
+        }
         message
     }
 
+    pub fn get_draft_chat_ids(&self) -> HashSet<String> {
+        self.drafts.keys().cloned().collect()
+    }
+
+    /// Moves the message input of the loaded chat to its draft and clears the input
+    pub fn stash_draft(&mut self) {
+        let Some(chat) = self.chat_manager.get_loaded_chat() else {
+            return;
+        };
+        let chat_internal_id = chat.internal_id.clone();
+        let draft = helpers::input_to_string(&self.message_input);
+        self.message_input.clear();
+        self.has_large_paste = false;
+
+        let changed = if draft.is_empty() {
+            self.drafts.remove(&chat_internal_id).is_some()
+        } else {
+            self.drafts.insert(chat_internal_id, draft.clone()) != Some(draft)
+        };
+        if changed {
+            self.save_drafts();
+        }
+    }
+
+    fn save_drafts(&mut self) {
+        if let Err(e) = storage::store_drafts(&self.drafts) {
+            self.save_error = Some(e);
+        }
+    }
+
+    pub fn take_save_error(&mut self) -> Option<String> {
+        self.save_error.take()
+    }
+
+    /// Puts the draft of the loaded chat into the message input
+    pub fn restore_draft(&mut self) {
+        let draft = self
+            .chat_manager
+            .get_loaded_chat()
+            .and_then(|chat| self.drafts.get(&chat.internal_id));
+        self.message_input = draft.map(|draft| helpers::string_to_input(draft)).unwrap_or_default();
+        self.line_editor.move_to_end(&self.message_input);
+    }
+
     pub fn set_active_input_entity(&mut self, active_input_entity: ActiveInputEntity) {
         self.active_input_entity = active_input_entity;
+        if active_input_entity == ActiveInputEntity::EnterMessage {
+            self.vim = VimState::default();
+            self.recalled_message = None;
+        }
         if active_input_entity != ActiveInputEntity::SelectChat {
             self.reset_cursor();
         }
@@ -85,101 +641,180 @@ impl MainWindow {
         self.reset_cursor();
     }
 
-    fn move_chat_cursor_up(&mut self) {
-        self.chat_manager.select_previous_chat();
-    }
-
-    fn move_chat_cursor_down(&mut self) {
-        self.chat_manager.select_next_chat();
+    /// Moves the selection in the chat list, the focus goes from the search box to the list
+    pub fn move_chat_cursor(&mut self, up: bool) {
+        self.set_active_input_entity(ActiveInputEntity::SelectChat);
+        if up {
+            self.move_chat_cursor_up();
+        } else {
+            self.move_chat_cursor_down();
+        }
     }
-}
 
-impl InputEntity for MainWindow {
-    fn process_input(&mut self, key_event: KeyEvent) {
-        match key_event.code {
-            KeyCode::Char(to_insert) => {
-                if self.get_active_input_entity() == ActiveInputEntity::SearchChats || self.get_active_input_entity() == ActiveInputEntity::EnterMessage {
-                    self.enter_char(to_insert);
-                }
-            }
-            KeyCode::Backspace => {
-                self.delete_char();
-            }
-            KeyCode::Left => {
-                self.move_cursor_left();
+    /// Closes the prompt or the chat, or steps back from the chat list to the search box and resets the search
+    pub fn back(&mut self) {
+        match self.active_input_entity {
+            ActiveInputEntity::SearchMessages => self.stop_chat_search(),
+            ActiveInputEntity::CommandLine => self.close_command_line(),
+            ActiveInputEntity::SearchSentMessages => self.stop_sent_message_search(),
+            ActiveInputEntity::EnterMessage => {
+                self.stash_draft();
+                self.chat_manager.unload_chat();
+                self.reset_message_view();
+                self.set_active_input_entity(ActiveInputEntity::SelectChat);
             }
-            KeyCode::Right => {
-                self.move_cursor_right();
+            ActiveInputEntity::SelectChat => {
+                self.chat_manager.unselect_chat();
+                self.chat_manager.get_message_search_results_mut().unselect();
+                self.set_active_input_entity(ActiveInputEntity::SearchChats);
+                self.reset_cursor();
             }
-            KeyCode::Up => {
-                if self.get_active_input_entity() != ActiveInputEntity::EnterMessage {
-                    self.set_active_input_entity(ActiveInputEntity::SelectChat);
-                    self.move_chat_cursor_up();
-                }
+            ActiveInputEntity::SearchChats => {
+                // todo method?
+                self.chat_manager.clear_search_results();
+                self.chat_manager.clear_message_search_results();
+                self.search_input.clear();
+                self.reset_cursor();
             }
-            KeyCode::Down => {
-                if self.get_active_input_entity() != ActiveInputEntity::EnterMessage {
-                    self.set_active_input_entity(ActiveInputEntity::SelectChat);
-                    self.move_chat_cursor_down();
-                }
+        }
+    }
+
+    fn move_chat_cursor_up(&mut self) {
+        match self.search_mode {
+            SearchMode::Users => self.chat_manager.select_previous_chat(),
+            SearchMode::Messages => self.chat_manager.get_message_search_results_mut().previous(),
+        }
+    }
+
+    pub fn select_first_item(&mut self) {
+        match self.search_mode {
+            SearchMode::Users => self.chat_manager.select_first_chat(),
+            SearchMode::Messages => self.chat_manager.get_message_search_results_mut().first(),
+        }
+    }
+
+    pub fn select_last_item(&mut self) {
+        match self.search_mode {
+            SearchMode::Users => self.chat_manager.select_last_chat(),
+            SearchMode::Messages => self.chat_manager.get_message_search_results_mut().last(),
+        }
+    }
+
+    /// Selects the item of the chat list at the screen row, returns false if there is no item there
+    pub fn select_list_item_at(&mut self, row: u16) -> bool {
+        let area = self.screen_areas.chats;
+        // +1 and -2 because of the borders
+        let Some(line) = row.checked_sub(area.y + 1).filter(|line| *line < area.height.saturating_sub(2)) else {
+            return false;
+        };
+        let line = line as usize;
+        let is_selected = match self.search_mode {
+            SearchMode::Users => {
+                let chats = self.chat_manager.get_active_chats_mut();
+                let index = chats.state.offset() + line;
+                chats.select_at(index)
             }
-            KeyCode::Esc => {
-                if self.get_active_input_entity() == ActiveInputEntity::EnterMessage {
-                    self.chat_manager.unload_chat();
-                    self.message_input.clear();
-                    self.set_active_input_entity(ActiveInputEntity::SelectChat);
-                } else if self.get_active_input_entity() == ActiveInputEntity::SelectChat {
-                    self.chat_manager.unselect_chat();
-                    self.set_active_input_entity(ActiveInputEntity::SearchChats);
-                    self.reset_cursor();
-                } else {
-                    // todo method?
-                    self.chat_manager.clear_search_results();
-                    self.search_input.clear();
-                    self.reset_cursor();
-                }
+            SearchMode::Messages => {
+                // a found message takes two lines
+                let hits = self.chat_manager.get_message_search_results_mut();
+                let index = hits.state.offset() + line / 2;
+                hits.select_at(index)
             }
-            // KeyCode::Tab => {
-            //     self.switch_to_next_input();
-            // }
-            _ => {}
         };
+        if is_selected {
+            self.set_active_input_entity(ActiveInputEntity::SelectChat);
+        }
+        is_selected
     }
 
-    fn enter_char(&mut self, new_char: char) {
-        let cursor_position = self.cursor_position;
-        self.get_active_input_mut().insert(cursor_position, new_char);
-
-        self.move_cursor_right();
+    pub fn select_filter_at(&mut self, column: u16) {
+        let index = self.screen_areas.filter_tabs.iter().position(|tab| tab.contains(&column));
+        if let Some(filter) = index.and_then(|index| self.chat_manager.get_filters().get(index).copied()) {
+            self.chat_manager.set_filter(filter);
+        }
     }
 
-    fn delete_char(&mut self) {
-        // todo duplicate code!!!
-        if self.cursor_position == 0 {
+    /// Returns the focus to the message input of the loaded chat, the loaded chat is selected in the list again
+    pub fn focus_message_input(&mut self) {
+        if matches!(self.active_input_entity, ActiveInputEntity::EnterMessage | ActiveInputEntity::SearchMessages | ActiveInputEntity::SearchSentMessages) {
             return;
         }
-
-        let cursor_position = self.cursor_position - 1;
-        self.get_active_input_mut().remove(cursor_position);
-        self.move_cursor_left();
+        let Some(chat) = self.chat_manager.get_loaded_chat() else {
+            return;
+        };
+        if chat.id.is_some() {
+            self.chat_manager.select_chat(chat.internal_id.clone());
+        }
+        self.set_active_input_entity(ActiveInputEntity::EnterMessage);
     }
 
-    fn move_cursor_left(&mut self) {
-        let cursor_moved_left = self.cursor_position.saturating_sub(1);
-        self.cursor_position = self.clamp_cursor(cursor_moved_left);
+    fn move_chat_cursor_down(&mut self) {
+        match self.search_mode {
+            SearchMode::Users => self.chat_manager.select_next_chat(),
+            SearchMode::Messages => self.chat_manager.get_message_search_results_mut().next(),
+        }
     }
+}
 
-    fn move_cursor_right(&mut self) {
-        let cursor_moved_right = self.cursor_position.saturating_add(1);
-        self.cursor_position = self.clamp_cursor(cursor_moved_right);
+impl InputEntity for MainWindow {
+    fn process_input(&mut self, key_event: KeyEvent) {
+        let is_searching_messages = self.get_active_input_entity() == ActiveInputEntity::SearchMessages;
+        let has_mention_suggestions = !self.get_mention_suggestions().is_empty();
+        match key_event.code {
+            KeyCode::Tab | KeyCode::Enter if has_mention_suggestions => {
+                self.complete_mention();
+                return;
+            }
+            KeyCode::Up | KeyCode::Down if has_mention_suggestions => {
+                self.move_mention_selection(key_event.code == KeyCode::Up);
+                return;
+            }
+            KeyCode::Esc if has_mention_suggestions => {
+                self.is_mention_dismissed = true;
+                return;
+            }
+            _ => {
+                self.is_mention_dismissed = false;
+            }
+        }
+        if self.get_active_input_entity() != ActiveInputEntity::SelectChat {
+            let (input, line_editor) = self.get_active_input_and_editor();
+            if line_editor.process(input, key_event) == Edit::TextChanged {
+                if is_searching_messages {
+                    self.update_chat_search();
+                } else if self.get_active_input_entity() == ActiveInputEntity::SearchSentMessages {
+                    self.update_sent_message_search();
+                }
+            }
+        }
     }
 
-    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.get_active_input().len())
+    fn paste(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n").replace('\r', "\n");
+        let text = match self.active_input_entity {
+            ActiveInputEntity::SelectChat => return,
+            ActiveInputEntity::EnterMessage => text,
+            // the other inputs are single-line
+            _ => text.replace('\n', " "),
+        };
+        let (input, line_editor) = self.get_active_input_and_editor();
+        line_editor.insert_str(input, &text);
+        match self.active_input_entity {
+            ActiveInputEntity::EnterMessage => {
+                self.has_large_paste |= text.chars().count() > LARGE_PASTE_CHARS || text.lines().count() > LARGE_PASTE_LINES;
+            }
+            ActiveInputEntity::SearchMessages => self.update_chat_search(),
+            ActiveInputEntity::SearchSentMessages => self.update_sent_message_search(),
+            _ => {}
+        }
     }
 
     fn reset_cursor(&mut self) {
-        self.cursor_position = self.get_active_input().len();
+        if self.active_input_entity == ActiveInputEntity::SelectChat {
+            return;
+        }
+        let (input, line_editor) = self.get_active_input_and_editor();
+        line_editor.move_to_end(input);
     }
 
     fn switch_to_next_input(&mut self) {}
@@ -1,17 +1,18 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
 
 use crate::app::App;
+use crate::keymap::{Action, Screen};
+use crate::window::main::ActiveInputEntity;
 
 pub mod login;
 pub mod main;
+pub mod vim;
 
 pub trait InputEntity {
     fn process_input(&mut self, key_event: KeyEvent);
-    fn enter_char(&mut self, new_char: char);
-    fn delete_char(&mut self);
-    fn move_cursor_left(&mut self);
-    fn move_cursor_right(&mut self);
-    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize;
+    /// Inserts the pasted text into the active input at once
+    fn paste(&mut self, text: &str);
     fn reset_cursor(&mut self);
     fn switch_to_next_input(&mut self);
     // todo this trait needs to take into account all possible inputs, bad design
@@ -19,18 +20,116 @@ pub trait InputEntity {
 }
 
 pub async fn process(app: &mut App, key_event: KeyEvent) {
-    match key_event.code {
-        // yes yes, I know, it's a hack and it'll work only for 3 languages: eng, ukr and rus
-        KeyCode::Char('c') | KeyCode::Char('C') | KeyCode::Char('с') | KeyCode::Char('С') => {
-            if key_event.modifiers == KeyModifiers::CONTROL {
-                app.quit()
-            } else {
-                app.pass_input_to_active_entity(key_event);
+    if app.main_window.is_confirming_send() {
+        match key_event.code {
+            KeyCode::Enter => {
+                app.main_window.confirm_send();
+                app.submit().await;
             }
+            KeyCode::Esc => app.main_window.cancel_send(),
+            _ if app.settings().keymap.is_bound(Action::Quit, key_event) => app.quit(),
+            _ => {}
         }
-        KeyCode::Enter => app.submit().await,
-        _ => {
-            app.pass_input_to_active_entity(key_event);
+        return;
+    }
+    if vim::process(app, key_event).await {
+        return;
+    }
+    // the @mention suggestions take the keys they need while they are shown
+    if matches!(key_event.code, KeyCode::Enter | KeyCode::Tab | KeyCode::Up | KeyCode::Down | KeyCode::Esc)
+        && !app.main_window.get_mention_suggestions().is_empty() {
+        app.pass_input_to_active_entity(key_event);
+        return;
+    }
+    let screen = get_screen(app);
+    let Some(action) = app.settings().keymap.get_action(screen, key_event) else {
+        app.pass_input_to_active_entity(key_event);
+        return;
+    };
+    match action {
+        Action::Quit => app.quit(),
+        Action::Back => app.main_window.back(),
+        Action::LogIn | Action::Search | Action::OpenChat | Action::SendMessage | Action::Accept => app.submit().await,
+        Action::NextInput => app.login_window.switch_to_next_input(),
+        Action::SwitchTab => app.login_window.switch_tabs(),
+        Action::SwitchSearchMode => app.main_window.switch_search_mode(),
+        Action::PreviousChat | Action::NextChat => app.main_window.move_chat_cursor(action == Action::PreviousChat),
+        Action::PreviousFilter => app.main_window.chat_manager.select_previous_filter(),
+        Action::NextFilter => app.main_window.chat_manager.select_next_filter(),
+        Action::TogglePin => app.toggle_selected_chat_pin().await,
+        Action::MovePinUp | Action::MovePinDown => app.move_selected_chat_pin(action == Action::MovePinUp).await,
+        Action::ToggleArchive => app.toggle_selected_chat_archive(),
+        Action::ToggleArchivedView => app.main_window.chat_manager.toggle_archived_view(),
+        Action::CycleMute => app.cycle_selected_chat_mute(),
+        Action::NewLine => app.main_window.enter_char('\n'),
+        Action::RetryFailed => app.retry_failed_messages().await,
+        Action::PreviousSentMessage | Action::NextSentMessage => app.recall_sent_message(action == Action::PreviousSentMessage),
+        Action::SearchSentMessages => match app.main_window.get_active_input_entity() {
+            ActiveInputEntity::SearchSentMessages => app.main_window.select_older_sent_message_match(),
+            ActiveInputEntity::EnterMessage => app.start_sent_message_search(),
+            _ => {}
+        },
+        Action::FindInChat => app.main_window.start_chat_search(),
+        Action::PreviousMatch | Action::NextMatch => {
+            if app.main_window.get_active_input_entity() == ActiveInputEntity::SearchMessages {
+                app.main_window.select_chat_search_match(action == Action::PreviousMatch);
+            }
         }
+        Action::ScrollUp => app.main_window.scroll_messages(-1),
+        Action::ScrollDown => app.main_window.scroll_messages(1),
+        Action::ScrollPageUp | Action::ScrollPageDown => app.main_window.scroll_messages_by_page(action == Action::ScrollPageUp),
+        Action::ScrollToTop => app.main_window.scroll_messages_to_line(0),
+        Action::ScrollToBottom => app.main_window.follow_newest_message(),
+    }
+}
+
+/// The screen the key bindings are looked up for
+fn get_screen(app: &App) -> Screen {
+    if !app.is_authenticated() {
+        return Screen::Login;
+    }
+    match app.main_window.get_active_input_entity() {
+        ActiveInputEntity::SearchChats => Screen::ChatSearch,
+        ActiveInputEntity::SelectChat => Screen::ChatList,
+        ActiveInputEntity::EnterMessage => Screen::MessageInput,
+        ActiveInputEntity::SearchMessages | ActiveInputEntity::CommandLine | ActiveInputEntity::SearchSentMessages => Screen::Prompt,
+    }
+}
+
+/// Lines scrolled by one step of the mouse wheel
+const MOUSE_SCROLL_LINES: isize = 3;
+
+pub async fn process_mouse(app: &mut App, mouse_event: MouseEvent) {
+    // the login window and the confirmation are used with the keyboard only
+    if !app.is_authenticated() || app.main_window.is_confirming_send() {
+        return;
+    }
+    let is_over = |area: Rect| {
+        (area.left()..area.right()).contains(&mouse_event.column) && (area.top()..area.bottom()).contains(&mouse_event.row)
     };
+    let areas = app.main_window.screen_areas.clone();
+    match mouse_event.kind {
+        MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+            let up = mouse_event.kind == MouseEventKind::ScrollUp;
+            if is_over(areas.messages) {
+                app.main_window.scroll_messages(if up { -MOUSE_SCROLL_LINES } else { MOUSE_SCROLL_LINES });
+            } else if is_over(areas.chats) {
+                app.main_window.move_chat_cursor(up);
+            }
+        }
+        MouseEventKind::Down(MouseButton::Left) => {
+            if is_over(areas.search) {
+                app.main_window.set_active_input_entity(ActiveInputEntity::SearchChats);
+            } else if is_over(areas.filters) {
+                app.main_window.select_filter_at(mouse_event.column);
+            } else if is_over(areas.chats) {
+                if app.main_window.select_list_item_at(mouse_event.row) {
+                    app.submit().await;
+                }
+            } else if is_over(areas.messages) || is_over(areas.message_input) {
+                app.main_window.focus_message_input();
+            }
+        }
+        _ => {}
+    }
 }
@@ -0,0 +1,236 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::app::App;
+use crate::helpers::line_editor::Motion;
+use crate::window::main::{ActiveInputEntity, MainWindow};
+
+/// Mode of the message input when vim mode is enabled in the settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VimMode {
+    /// Keys are typed into the input, a chat is opened in this mode
+    #[default]
+    Insert,
+    Normal,
+    /// Selection from the anchor to the cursor, both included
+    Visual { anchor: usize },
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VimState {
+    pub mode: VimMode,
+    /// First key of a two-key command like `gg` or `dd`
+    pending: Option<char>,
+}
+
+/// Handles the key if vim mode is enabled. Returns false if the key is left to the usual key handling
+pub async fn process(app: &mut App, key_event: KeyEvent) -> bool {
+    if !app.settings().vim || !app.is_authenticated() {
+        return false;
+    }
+    match app.main_window.get_active_input_entity() {
+        ActiveInputEntity::SelectChat => process_chat_list(app, key_event).await,
+        ActiveInputEntity::EnterMessage => {
+            let main_window = &mut app.main_window;
+            match main_window.vim.mode {
+                // Esc dismisses the @mention suggestions first
+                VimMode::Insert if key_event.code == KeyCode::Esc && main_window.get_mention_suggestions().is_empty() => {
+                    enter_normal_mode(main_window);
+                    true
+                }
+                VimMode::Insert => false,
+                VimMode::Normal => process_normal(main_window, key_event),
+                VimMode::Visual { anchor } => process_visual(main_window, key_event, anchor),
+            }
+        }
+        _ => false,
+    }
+}
+
+async fn process_chat_list(app: &mut App, key_event: KeyEvent) -> bool {
+    let Some(key) = get_plain_char(key_event) else {
+        app.main_window.vim.pending = None;
+        return false;
+    };
+    match (app.main_window.vim.pending.take(), key) {
+        (_, 'j') => app.main_window.move_chat_cursor(false),
+        (_, 'k') => app.main_window.move_chat_cursor(true),
+        (Some('g'), 'g') => app.main_window.select_first_item(),
+        (_, 'g') => app.main_window.vim.pending = Some('g'),
+        (_, 'G') => app.main_window.select_last_item(),
+        (_, 'l') => app.submit().await,
+        (_, '/') => app.main_window.set_active_input_entity(ActiveInputEntity::SearchChats),
+        (_, ':') => app.main_window.open_command_line(),
+        _ => return false,
+    }
+    true
+}
+
+fn process_normal(main_window: &mut MainWindow, key_event: KeyEvent) -> bool {
+    if key_event.modifiers == KeyModifiers::CONTROL {
+        match key_event.code {
+            KeyCode::Char('u') => main_window.scroll_messages_by_page(true),
+            KeyCode::Char('d') => main_window.scroll_messages_by_page(false),
+            _ => return false,
+        }
+        return true;
+    }
+    let Some(key) = get_plain_char(key_event) else {
+        main_window.vim.pending = None;
+        return false;
+    };
+    match (main_window.vim.pending.take(), key) {
+        (Some('g'), 'g') => main_window.scroll_messages_to_line(0),
+        (Some('d'), 'd') => kill_line(main_window),
+        (Some('c'), 'c') => {
+            kill_between(main_window, Some(Motion::LineStart), Some(Motion::LineEnd));
+            main_window.vim.mode = VimMode::Insert;
+        }
+        (Some('d'), 'w') => kill_between(main_window, None, Some(Motion::NextWordStart)),
+        (Some('d'), 'b') => kill_between(main_window, Some(Motion::PreviousWordStart), None),
+        (Some('c'), 'w') => {
+            kill_between(main_window, None, Some(Motion::NextWordEnd));
+            main_window.vim.mode = VimMode::Insert;
+        }
+        // unknown two-key commands are dropped like in vim
+        (Some(_), _) => {}
+        (None, 'g' | 'd' | 'c') => main_window.vim.pending = Some(key),
+        (None, 'j') => main_window.scroll_messages(1),
+        (None, 'k') => main_window.scroll_messages(-1),
+        (None, 'G') => main_window.follow_newest_message(),
+        (None, 'x') => kill_between(main_window, None, Some(Motion::Right)),
+        (None, 'X') => kill_between(main_window, Some(Motion::Left), None),
+        (None, 'D') => kill_between(main_window, None, Some(Motion::LineEnd)),
+        (None, 'C') => {
+            kill_between(main_window, None, Some(Motion::LineEnd));
+            main_window.vim.mode = VimMode::Insert;
+        }
+        (None, 'p') => main_window.edit_message_input(|input, line_editor| {
+            line_editor.move_cursor(input, Motion::Right);
+            line_editor.paste(input);
+        }),
+        (None, 'P') => main_window.edit_message_input(|input, line_editor| line_editor.paste(input)),
+        (None, 'i') => main_window.vim.mode = VimMode::Insert,
+        (None, 'a' | 'I' | 'A') => {
+            let motion = match key {
+                'a' => Motion::Right,
+                'I' => Motion::LineStart,
+                _ => Motion::LineEnd,
+            };
+            main_window.edit_message_input(|input, line_editor| line_editor.move_cursor(input, motion));
+            main_window.vim.mode = VimMode::Insert;
+        }
+        (None, 'o') => {
+            main_window.edit_message_input(|input, line_editor| {
+                line_editor.move_cursor(input, Motion::LineEnd);
+                line_editor.insert(input, '\n');
+            });
+            main_window.vim.mode = VimMode::Insert;
+        }
+        (None, 'O') => {
+            main_window.edit_message_input(|input, line_editor| {
+                line_editor.move_cursor(input, Motion::LineStart);
+                line_editor.insert(input, '\n');
+                line_editor.move_cursor(input, Motion::Left);
+            });
+            main_window.vim.mode = VimMode::Insert;
+        }
+        (None, 'v') => {
+            let anchor = main_window.get_cursor_position();
+            main_window.vim.mode = VimMode::Visual { anchor };
+        }
+        (None, '/') => main_window.start_chat_search(),
+        (None, ':') => main_window.open_command_line(),
+        (None, key) => {
+            if let Some(motion) = get_motion(key) {
+                main_window.edit_message_input(|input, line_editor| line_editor.move_cursor(input, motion));
+            }
+        }
+    }
+    true
+}
+
+fn process_visual(main_window: &mut MainWindow, key_event: KeyEvent, anchor: usize) -> bool {
+    let Some(key) = get_plain_char(key_event) else {
+        if key_event.code == KeyCode::Esc {
+            main_window.vim.mode = VimMode::Normal;
+            return true;
+        }
+        return false;
+    };
+    let cursor = main_window.get_cursor_position();
+    let (start, end) = (anchor.min(cursor), anchor.max(cursor) + 1);
+    match key {
+        'd' | 'x' => {
+            main_window.edit_message_input(|input, line_editor| line_editor.kill_range(input, start, end));
+            main_window.vim.mode = VimMode::Normal;
+        }
+        'c' => {
+            main_window.edit_message_input(|input, line_editor| line_editor.kill_range(input, start, end));
+            main_window.vim.mode = VimMode::Insert;
+        }
+        'y' => {
+            main_window.edit_message_input(|input, line_editor| {
+                line_editor.copy_range(input, start, end);
+                line_editor.set_cursor(input, start);
+            });
+            main_window.vim.mode = VimMode::Normal;
+        }
+        'v' => main_window.vim.mode = VimMode::Normal,
+        key => {
+            if let Some(motion) = get_motion(key) {
+                main_window.edit_message_input(|input, line_editor| line_editor.move_cursor(input, motion));
+            }
+        }
+    }
+    true
+}
+
+/// Leaving the insert mode moves the cursor onto the last typed character like in vim
+fn enter_normal_mode(main_window: &mut MainWindow) {
+    main_window.edit_message_input(|input, line_editor| {
+        if line_editor.target(input, Motion::LineStart) < line_editor.cursor() {
+            line_editor.move_cursor(input, Motion::Left);
+        }
+    });
+    main_window.vim.mode = VimMode::Normal;
+}
+
+/// Kills the text between the targets of the motions, None stands for the cursor
+fn kill_between(main_window: &mut MainWindow, from: Option<Motion>, to: Option<Motion>) {
+    main_window.edit_message_input(|input, line_editor| {
+        let start = from.map_or(line_editor.cursor(), |motion| line_editor.target(input, motion));
+        let end = to.map_or(line_editor.cursor(), |motion| line_editor.target(input, motion));
+        if start < end {
+            line_editor.kill_range(input, start, end);
+        }
+    });
+}
+
+/// Kills the line with its line break
+fn kill_line(main_window: &mut MainWindow) {
+    main_window.edit_message_input(|input, line_editor| {
+        let start = line_editor.target(input, Motion::LineStart);
+        let end = line_editor.target(input, Motion::LineEnd) + 1;
+        line_editor.kill_range(input, start, end);
+    });
+}
+
+fn get_motion(key: char) -> Option<Motion> {
+    match key {
+        'h' => Some(Motion::Left),
+        'l' => Some(Motion::Right),
+        'w' => Some(Motion::NextWordStart),
+        'b' => Some(Motion::PreviousWordStart),
+        'e' => Some(Motion::NextWordEnd),
+        '0' | '^' => Some(Motion::LineStart),
+        '$' => Some(Motion::LineEnd),
+        _ => None,
+    }
+}
+
+/// The typed character if no modifiers other than Shift are held
+fn get_plain_char(key_event: KeyEvent) -> Option<char> {
+    match key_event.code {
+        KeyCode::Char(key) if key_event.modifiers.difference(KeyModifiers::SHIFT).is_empty() => Some(key),
+        _ => None,
+    }
+}